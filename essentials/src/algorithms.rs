@@ -0,0 +1,326 @@
+//! 基于 Point 的几何算法：凸包与最近点对
+//! 坐标为 NaN 或无穷大的点没有确定的方向和距离，所有公开函数都会先忽略这些点；
+//! 方向判断全部使用精确的 orient2d。
+use crate::geometry::{orient2d, Orientation};
+use crate::smart_pointer::Point;
+use std::cmp::Ordering;
+
+/// 只用于 finite_points 过滤后的坐标及其距离，不会遇到 NaN
+fn cmp_f64(a: f64, b: f64) -> Ordering {
+    a.partial_cmp(&b).expect("NaN coordinate")
+}
+
+fn finite_points(points: &[Point]) -> Vec<Point> {
+    points
+        .iter()
+        .copied()
+        .filter(|p| p.x.is_finite() && p.y.is_finite())
+        .collect()
+}
+
+fn cmp_xy(a: &Point, b: &Point) -> Ordering {
+    cmp_f64(a.x, b.x).then(cmp_f64(a.y, b.y))
+}
+
+fn is_ccw(a: Point, b: Point, c: Point) -> bool {
+    orient2d(a, b, c) == Orientation::CounterClockwise
+}
+
+/// Andrew 单调链算法，O(n log n)
+/// 返回逆时针排列的凸包顶点，从 x 最小（其次 y 最小）的点开始，不包含边上的共线点和重复点
+pub fn convex_hull(points: &[Point]) -> Vec<Point> {
+    let mut pts = finite_points(points);
+    pts.sort_by(cmp_xy);
+    pts.dedup_by(|a, b| a.coincides(*b));
+    if pts.len() < 3 {
+        return pts;
+    }
+
+    // 下凸壳从左到右，上凸壳从右到左，每条链都去掉最后一个点，它是另一条链的起点
+    let mut hull = Vec::with_capacity(pts.len() + 1);
+    monotone_chain(&mut hull, pts.iter());
+    monotone_chain(&mut hull, pts.iter().rev());
+    hull
+}
+
+fn monotone_chain<'a>(hull: &mut Vec<Point>, pts: impl Iterator<Item = &'a Point>) {
+    let start = hull.len();
+    for &p in pts {
+        while hull.len() >= start + 2 && !is_ccw(hull[hull.len() - 2], hull[hull.len() - 1], p) {
+            hull.pop();
+        }
+        hull.push(p);
+    }
+    hull.pop();
+}
+
+/// Graham 扫描算法，O(n log n)，结果与 convex_hull 完全相同
+pub fn graham_scan(points: &[Point]) -> Vec<Point> {
+    let mut pts = finite_points(points);
+    pts.sort_by(cmp_xy);
+    pts.dedup_by(|a, b| a.coincides(*b));
+    if pts.len() < 3 {
+        return pts;
+    }
+
+    // 以 y 最小（其次 x 最小）的点为极点，其余点按极角排序，极角相同按距离排序
+    let pivot_idx = (0..pts.len())
+        .min_by(|&i, &j| cmp_f64(pts[i].y, pts[j].y).then(cmp_f64(pts[i].x, pts[j].x)))
+        .unwrap();
+    let pivot = pts.swap_remove(pivot_idx);
+    let dist = |p: &Point| (*p - pivot).dot(*p - pivot);
+    pts.sort_by(|a, b| match orient2d(pivot, *a, *b) {
+        Orientation::CounterClockwise => Ordering::Less,
+        Orientation::Clockwise => Ordering::Greater,
+        Orientation::Collinear => cmp_f64(dist(a), dist(b)),
+    });
+    // 最后一条射线上的共线点需要按距离从远到近访问，否则近处的点会留在凸包上
+    let last = pts[pts.len() - 1];
+    let tail = pts
+        .iter()
+        .rev()
+        .take_while(|&&p| orient2d(pivot, p, last) == Orientation::Collinear)
+        .count();
+    let len = pts.len();
+    if tail < len {
+        pts[len - tail..].reverse();
+    }
+
+    let mut hull = vec![pivot];
+    for &p in pts.iter() {
+        while hull.len() >= 2 && !is_ccw(hull[hull.len() - 2], hull[hull.len() - 1], p) {
+            hull.pop();
+        }
+        hull.push(p);
+    }
+    while hull.len() >= 3 && !is_ccw(hull[hull.len() - 2], hull[hull.len() - 1], pivot) {
+        hull.pop();
+    }
+
+    // 旋转到与单调链相同的起点
+    let start = (0..hull.len())
+        .min_by(|&i, &j| cmp_xy(&hull[i], &hull[j]))
+        .unwrap();
+    hull.rotate_left(start);
+    hull
+}
+
+fn dist2(a: Point, b: Point) -> f64 {
+    (a - b).dot(a - b)
+}
+
+/// 分治法求最近点对，O(n log n)，有限坐标的点少于两个时返回 None
+pub fn closest_pair(points: &[Point]) -> Option<(Point, Point)> {
+    let mut by_x = finite_points(points);
+    if by_x.len() < 2 {
+        return None;
+    }
+    by_x.sort_by(cmp_xy);
+    let mut buf = by_x.clone();
+    let (_, pair) = closest_rec(&mut by_x, &mut buf);
+    Some(pair)
+}
+
+/// 进入时 pts 按 x 排序，返回时 pts 按 y 排序（归并排序的思路），
+/// 这样合并阶段不需要再排序，整体保持 O(n log n)
+fn closest_rec(pts: &mut [Point], buf: &mut [Point]) -> (f64, (Point, Point)) {
+    let n = pts.len();
+    if n <= 3 {
+        let mut best = (f64::INFINITY, (pts[0], pts[0]));
+        for i in 0..n {
+            for j in i + 1..n {
+                let d = dist2(pts[i], pts[j]);
+                if d < best.0 {
+                    best = (d, (pts[i], pts[j]));
+                }
+            }
+        }
+        pts.sort_by(|a, b| cmp_f64(a.y, b.y));
+        return best;
+    }
+
+    let mid = n / 2;
+    let mid_x = pts[mid].x;
+    let (left, right) = pts.split_at_mut(mid);
+    let l = closest_rec(left, buf);
+    let r = closest_rec(right, buf);
+    let mut best = if l.0 <= r.0 { l } else { r };
+
+    // 按 y 归并左右两半
+    let (mut i, mut j) = (0, mid);
+    for slot in buf[..n].iter_mut() {
+        if j >= n || (i < mid && pts[i].y <= pts[j].y) {
+            *slot = pts[i];
+            i += 1;
+        } else {
+            *slot = pts[j];
+            j += 1;
+        }
+    }
+    pts.copy_from_slice(&buf[..n]);
+
+    // 分界线两侧宽度为当前最短距离的带状区域，每个点只需与 y 方向上相近的少数点比较
+    let mut k = 0;
+    for &p in pts.iter() {
+        if (p.x - mid_x).powi(2) < best.0 {
+            buf[k] = p;
+            k += 1;
+        }
+    }
+    for i in 0..k {
+        for j in i + 1..k {
+            if (buf[j].y - buf[i].y).powi(2) >= best.0 {
+                break;
+            }
+            let d = dist2(buf[i], buf[j]);
+            if d < best.0 {
+                best = (d, (buf[i], buf[j]));
+            }
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{closest_pair, cmp_xy, convex_hull, dist2, graham_scan};
+    use crate::geometry::{orient2d, Location, Orientation, Polygon};
+    use crate::smart_pointer::Point;
+    use crate::test_util::Rng;
+
+    fn p(x: f64, y: f64) -> Point {
+        Point::new(x, y)
+    }
+
+    /// 暴力凸包：有向边 (a, b) 是凸包的边，当且仅当其他点都在它左侧或落在线段上
+    fn brute_force_hull(points: &[Point]) -> Vec<Point> {
+        let mut vertices = Vec::new();
+        for &a in points {
+            for &b in points {
+                if a == b {
+                    continue;
+                }
+                let is_edge = points.iter().all(|&c| match orient2d(a, b, c) {
+                    Orientation::CounterClockwise => true,
+                    Orientation::Clockwise => false,
+                    Orientation::Collinear => crate::geometry::Segment::new(a, b).contains(c),
+                });
+                if is_edge {
+                    vertices.push(a);
+                }
+            }
+        }
+        // 所有点都重合时没有边
+        if vertices.is_empty() {
+            vertices.extend(points.first());
+        }
+        vertices.sort_by(cmp_xy);
+        vertices.dedup();
+        vertices
+    }
+
+    fn brute_force_closest(points: &[Point]) -> f64 {
+        let mut best = f64::INFINITY;
+        for i in 0..points.len() {
+            for j in i + 1..points.len() {
+                best = best.min(dist2(points[i], points[j]));
+            }
+        }
+        best
+    }
+
+    fn check_hull(points: &[Point], hull: &[Point]) {
+        let mut sorted = hull.to_vec();
+        sorted.sort_by(cmp_xy);
+        assert_eq!(sorted, brute_force_hull(points));
+        if hull.len() >= 3 {
+            for i in 0..hull.len() {
+                let (a, b, c) = (
+                    hull[i],
+                    hull[(i + 1) % hull.len()],
+                    hull[(i + 2) % hull.len()],
+                );
+                assert_eq!(orient2d(a, b, c), Orientation::CounterClockwise);
+            }
+            let poly = Polygon::new(hull.to_vec());
+            assert!(points.iter().all(|&q| poly.locate(q) != Location::Outside));
+        }
+    }
+
+    #[test]
+    fn test_convex_hull_small() {
+        let square = vec![
+            p(0.0, 0.0),
+            p(1.0, 1.0),
+            p(2.0, 2.0),
+            p(2.0, 0.0),
+            p(0.0, 2.0),
+            p(1.0, 0.0),
+            p(0.0, 1.0),
+            p(2.0, 2.0),
+        ];
+        let expected = vec![p(0.0, 0.0), p(2.0, 0.0), p(2.0, 2.0), p(0.0, 2.0)];
+        assert_eq!(convex_hull(&square), expected);
+        assert_eq!(graham_scan(&square), expected);
+
+        let line = vec![p(1.0, 1.0), p(3.0, 3.0), p(2.0, 2.0), p(0.0, 0.0)];
+        assert_eq!(convex_hull(&line), vec![p(0.0, 0.0), p(3.0, 3.0)]);
+        assert_eq!(graham_scan(&line), vec![p(0.0, 0.0), p(3.0, 3.0)]);
+        assert_eq!(convex_hull(&[p(1.0, 1.0), p(1.0, 1.0)]), vec![p(1.0, 1.0)]);
+        assert!(graham_scan(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_convex_hull_random() {
+        let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+        for round in 0..200 {
+            let n = 1 + round % 60;
+            let grid = if round % 2 == 0 { Some(8) } else { None };
+            let points = rng.cloud(n, grid);
+            let hull = convex_hull(&points);
+            check_hull(&points, &hull);
+            assert_eq!(graham_scan(&points), hull);
+        }
+    }
+
+    #[test]
+    fn test_closest_pair_random() {
+        assert_eq!(closest_pair(&[p(0.0, 0.0)]), None);
+        let mut rng = Rng(42);
+        for round in 0..200 {
+            let n = 2 + round % 150;
+            let grid = if round % 3 == 0 { Some(50) } else { None };
+            let points = rng.cloud(n, grid);
+            let (a, b) = closest_pair(&points).unwrap();
+            assert_eq!(dist2(a, b), brute_force_closest(&points));
+            assert!(points.contains(&a) && points.contains(&b));
+        }
+        // 有重复点时最近距离为 0
+        let pair = closest_pair(&[p(5.0, 1.0), p(0.0, 0.0), p(5.0, 1.0), p(9.0, 9.0)]);
+        assert_eq!(pair, Some((p(5.0, 1.0), p(5.0, 1.0))));
+    }
+
+    #[test]
+    fn test_non_finite_points_are_ignored() {
+        let square = [
+            p(0.0, 0.0),
+            p(4.0, 0.0),
+            p(4.0, 4.0),
+            p(0.0, 4.0),
+            p(1.0, 1.0),
+        ];
+        let mut points = square.to_vec();
+        points.extend([
+            p(f64::NAN, 0.0),
+            p(2.0, f64::NAN),
+            p(f64::INFINITY, 1.0),
+            p(3.0, f64::NEG_INFINITY),
+        ]);
+        let hull = convex_hull(&square);
+        assert_eq!(convex_hull(&points), hull);
+        assert_eq!(graham_scan(&points), hull);
+        assert_eq!(closest_pair(&points), closest_pair(&square));
+        assert_eq!(closest_pair(&[p(f64::NAN, f64::NAN), p(1.0, 1.0)]), None);
+        assert!(convex_hull(&[p(f64::NAN, 0.0); 4]).is_empty());
+    }
+}
@@ -0,0 +1,44 @@
+//! 计算器 REPL：cargo run --bin calc
+//! 每行一个表达式或 `name = 表达式`，:vars 列出变量，:quit 或 EOF 退出
+// REPL 只用到 Calculator，库里其余的接口由主程序和测试覆盖
+#[path = "../calc.rs"]
+#[allow(dead_code)]
+mod calc;
+
+use calc::Calculator;
+use std::io::{self, BufRead, Write};
+
+fn main() {
+    let mut calc = Calculator::new();
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+    loop {
+        print!("> ");
+        stdout.flush().expect("failed to flush stdout");
+        let mut line = String::new();
+        match stdin.lock().read_line(&mut line) {
+            Ok(0) => {
+                println!();
+                break;
+            }
+            Ok(_) => {}
+            Err(e) => {
+                eprintln!("error: {}", e);
+                break;
+            }
+        }
+        match line.trim() {
+            ":quit" | ":q" => break,
+            ":vars" => {
+                for (name, value) in calc.vars() {
+                    println!("{} = {}", name, value);
+                }
+            }
+            line => match calc.eval_line(line) {
+                Ok(Some(value)) => println!("{}", value),
+                Ok(None) => {}
+                Err(e) => println!("error: {}", e),
+            },
+        }
+    }
+}
@@ -0,0 +1,839 @@
+//! 整数计算器：词法分析、Pratt 解析和求值
+//! 二元运算符登记在 Operators 中，每个运算符有优先级、结合性和一个 fn(i32, i32) -> i32，
+//! 求值时和 math(op, a, b) 一样通过函数指针调用，新增运算符只需要再登记一次。
+//! 函数指针本身只处理合法输入：调用之前先执行同样登记的检查函数，
+//! 溢出、除以零等情况在这里变成错误返回，而不是在运算中 panic 或回绕。
+//! 内置运算符直接登记 checked 运算，检查和计算只做一次。
+//!
+//! Pratt 解析中每个运算符有左右两个绑定强度，左结合运算符右侧更强，右结合运算符左侧更强，
+//! 所以 1 - 2 - 3 是 (1 - 2) - 3，而 2 ^ 3 ^ 2 是 2 ^ (3 ^ 2)。
+//! 取负的优先级高于乘除、低于乘方：-2 ^ 2 是 -(2 ^ 2)，取负直接用 checked_neg 求值，不经过 "-"。
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CalcError {
+    /// 位置是输入中的字节偏移
+    UnexpectedChar {
+        ch: char,
+        pos: usize,
+    },
+    NumberTooLarge {
+        pos: usize,
+    },
+    UnexpectedToken {
+        found: String,
+        pos: usize,
+    },
+    UnexpectedEnd,
+    UnknownVariable(String),
+    /// 语法树中的运算符没有登记，只会出现在手工构造的 Expr 中
+    UnknownOperator(String),
+    Overflow {
+        op: String,
+        lhs: i32,
+        rhs: i32,
+    },
+    DivisionByZero,
+    NegativeExponent,
+    /// 嵌套超过 MAX_DEPTH 层
+    TooDeep {
+        pos: usize,
+    },
+}
+
+impl fmt::Display for CalcError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CalcError::UnexpectedChar { ch, pos } => {
+                write!(f, "unexpected character `{}` at {}", ch, pos)
+            }
+            CalcError::NumberTooLarge { pos } => write!(f, "number at {} is too large", pos),
+            CalcError::UnexpectedToken { found, pos } => {
+                write!(f, "unexpected `{}` at {}", found, pos)
+            }
+            CalcError::UnexpectedEnd => write!(f, "unexpected end of input"),
+            CalcError::UnknownVariable(name) => write!(f, "unknown variable `{}`", name),
+            CalcError::UnknownOperator(symbol) => write!(f, "unknown operator `{}`", symbol),
+            CalcError::Overflow { op, lhs, rhs } => {
+                write!(f, "overflow in {} {} {}", lhs, op, rhs)
+            }
+            CalcError::DivisionByZero => write!(f, "division by zero"),
+            CalcError::NegativeExponent => write!(f, "negative exponent"),
+            CalcError::TooDeep { pos } => write!(f, "expression at {} is nested too deeply", pos),
+        }
+    }
+}
+
+impl Error for CalcError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Assoc {
+    Left,
+    Right,
+}
+
+#[derive(Clone, Copy)]
+pub struct Operator {
+    /// 越大结合得越紧，取负的优先级是 PREFIX_PRECEDENCE
+    pub precedence: u8,
+    pub assoc: Assoc,
+    func: Func,
+}
+
+#[derive(Clone, Copy)]
+enum Func {
+    /// check 通过之后才调用 func
+    Checked {
+        func: fn(i32, i32) -> i32,
+        check: fn(i32, i32) -> Result<(), CalcError>,
+    },
+    /// 运算自己报告错误
+    Fallible(fn(i32, i32) -> Result<i32, CalcError>),
+}
+
+impl Operator {
+    /// check 在 func 之前调用，必须拒绝所有会让 func panic 的输入，
+    /// 比如溢出、除以零、移位位数过大，检查通过后 func 的结果就是运算结果
+    pub fn new(
+        precedence: u8,
+        assoc: Assoc,
+        func: fn(i32, i32) -> i32,
+        check: fn(i32, i32) -> Result<(), CalcError>,
+    ) -> Self {
+        Operator {
+            precedence,
+            assoc,
+            func: Func::Checked { func, check },
+        }
+    }
+
+    /// 运算本身就是 checked 的，直接返回结果或错误
+    pub fn fallible(
+        precedence: u8,
+        assoc: Assoc,
+        func: fn(i32, i32) -> Result<i32, CalcError>,
+    ) -> Self {
+        Operator {
+            precedence,
+            assoc,
+            func: Func::Fallible(func),
+        }
+    }
+
+    pub fn call(&self, lhs: i32, rhs: i32) -> Result<i32, CalcError> {
+        match self.func {
+            Func::Checked { func, check } => {
+                check(lhs, rhs)?;
+                Ok(func(lhs, rhs))
+            }
+            Func::Fallible(func) => func(lhs, rhs),
+        }
+    }
+
+    /// Pratt 解析中的左右绑定强度，用 u16 计算，任何 u8 优先级都不会溢出
+    fn binding_power(&self) -> (u16, u16) {
+        let p = u16::from(self.precedence) * 2;
+        match self.assoc {
+            Assoc::Left => (p, p + 1),
+            Assoc::Right => (p + 1, p),
+        }
+    }
+}
+
+pub const PREFIX_PRECEDENCE: u8 = 3;
+
+/// 括号、取负和运算符链的嵌套层数上限。解析、求值和释放语法树都是递归的，
+/// 不加限制时一行几十万个 ( 就会耗尽栈空间
+pub const MAX_DEPTH: usize = 256;
+
+/// checked 运算的结果为 None 时报告溢出
+fn overflow(op: &str, lhs: i32, rhs: i32, result: Option<i32>) -> Result<i32, CalcError> {
+    result.ok_or_else(|| CalcError::Overflow {
+        op: op.to_string(),
+        lhs,
+        rhs,
+    })
+}
+
+fn add(a: i32, b: i32) -> Result<i32, CalcError> {
+    overflow("+", a, b, a.checked_add(b))
+}
+
+fn sub(a: i32, b: i32) -> Result<i32, CalcError> {
+    overflow("-", a, b, a.checked_sub(b))
+}
+
+fn mul(a: i32, b: i32) -> Result<i32, CalcError> {
+    overflow("*", a, b, a.checked_mul(b))
+}
+
+fn div(a: i32, b: i32) -> Result<i32, CalcError> {
+    match b {
+        0 => Err(CalcError::DivisionByZero),
+        _ => overflow("/", a, b, a.checked_div(b)),
+    }
+}
+
+fn rem(a: i32, b: i32) -> Result<i32, CalcError> {
+    match b {
+        0 => Err(CalcError::DivisionByZero),
+        _ => overflow("%", a, b, a.checked_rem(b)),
+    }
+}
+
+fn pow(a: i32, b: i32) -> Result<i32, CalcError> {
+    match b {
+        b if b < 0 => Err(CalcError::NegativeExponent),
+        _ => overflow("^", a, b, a.checked_pow(b as u32)),
+    }
+}
+
+/// 按符号登记的二元运算符
+#[derive(Clone)]
+pub struct Operators {
+    table: HashMap<String, Operator>,
+}
+
+impl Operators {
+    /// 没有任何运算符
+    pub fn empty() -> Self {
+        Operators {
+            table: HashMap::new(),
+        }
+    }
+
+    /// 登记或替换一个运算符，符号不能包含数字、字母、空白、括号和 =。
+    /// 用 Operator::new 登记时，check 必须拒绝所有会让 func panic 的输入
+    pub fn register(&mut self, symbol: &str, op: Operator) {
+        assert!(
+            !symbol.is_empty()
+                && symbol
+                    .chars()
+                    .all(|c| !c.is_alphanumeric() && !c.is_whitespace() && !"()=_".contains(c)),
+            "invalid operator symbol `{}`",
+            symbol
+        );
+        self.table.insert(symbol.to_string(), op);
+    }
+
+    /// 移除一个运算符并返回它，之后这个符号在词法分析时就是非法字符
+    pub fn unregister(&mut self, symbol: &str) -> Option<Operator> {
+        self.table.remove(symbol)
+    }
+
+    pub fn get(&self, symbol: &str) -> Option<&Operator> {
+        self.table.get(symbol)
+    }
+
+    /// 从 s 的开头匹配最长的运算符符号，这样 ** 不会被拆成两个 *
+    fn longest_match(&self, s: &str) -> Option<&str> {
+        self.table
+            .keys()
+            .filter(|symbol| s.starts_with(symbol.as_str()))
+            .max_by_key(|symbol| symbol.len())
+            .map(String::as_str)
+    }
+
+    pub fn apply(&self, symbol: &str, lhs: i32, rhs: i32) -> Result<i32, CalcError> {
+        self.get(symbol)
+            .ok_or_else(|| CalcError::UnknownOperator(symbol.to_string()))?
+            .call(lhs, rhs)
+    }
+}
+
+/// + - * / % ^，^ 是右结合的乘方
+impl Default for Operators {
+    fn default() -> Self {
+        let mut ops = Operators::empty();
+        ops.register("+", Operator::fallible(1, Assoc::Left, add));
+        ops.register("-", Operator::fallible(1, Assoc::Left, sub));
+        ops.register("*", Operator::fallible(2, Assoc::Left, mul));
+        ops.register("/", Operator::fallible(2, Assoc::Left, div));
+        ops.register("%", Operator::fallible(2, Assoc::Left, rem));
+        ops.register("^", Operator::fallible(4, Assoc::Right, pow));
+        ops
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Token {
+    Num(i32),
+    Ident(String),
+    Op(String),
+    LParen,
+    RParen,
+    Assign,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Num(n) => write!(f, "{}", n),
+            Token::Ident(name) => write!(f, "{}", name),
+            Token::Op(symbol) => write!(f, "{}", symbol),
+            Token::LParen => write!(f, "("),
+            Token::RParen => write!(f, ")"),
+            Token::Assign => write!(f, "="),
+        }
+    }
+}
+
+/// 词法分析，每个词法单元带上它在输入中的字节偏移；运算符按已登记的符号识别
+pub fn tokenize(input: &str, ops: &Operators) -> Result<Vec<(Token, usize)>, CalcError> {
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+    while let Some(c) = input[pos..].chars().next() {
+        let rest = &input[pos..];
+        let start = pos;
+        let token = if c.is_whitespace() {
+            pos += c.len_utf8();
+            continue;
+        } else if c.is_ascii_digit() {
+            let len = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            pos += len;
+            let n = rest[..len]
+                .parse()
+                .map_err(|_| CalcError::NumberTooLarge { pos: start })?;
+            Token::Num(n)
+        } else if c.is_alphabetic() || c == '_' {
+            let len = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            pos += len;
+            Token::Ident(rest[..len].to_string())
+        } else if let Some(symbol) = ops.longest_match(rest) {
+            pos += symbol.len();
+            Token::Op(symbol.to_string())
+        } else {
+            pos += c.len_utf8();
+            match c {
+                '(' => Token::LParen,
+                ')' => Token::RParen,
+                '=' => Token::Assign,
+                _ => return Err(CalcError::UnexpectedChar { ch: c, pos: start }),
+            }
+        };
+        tokens.push((token, start));
+    }
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Num(i32),
+    Var(String),
+    Neg(Box<Expr>),
+    Binary {
+        op: String,
+        lhs: Box<Expr>,
+        rhs: Box<Expr>,
+    },
+}
+
+impl fmt::Display for Expr {
+    /// 完全加上括号的形式，用来检查优先级和结合性
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expr::Num(n) => write!(f, "{}", n),
+            Expr::Var(name) => write!(f, "{}", name),
+            Expr::Neg(e) => write!(f, "(-{})", e),
+            Expr::Binary { op, lhs, rhs } => write!(f, "({} {} {})", lhs, op, rhs),
+        }
+    }
+}
+
+struct Parser<'a> {
+    tokens: &'a [(Token, usize)],
+    pos: usize,
+    ops: &'a Operators,
+    /// 当前 expr 的递归层数
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(t, _)| t)
+    }
+
+    fn unexpected(&self) -> CalcError {
+        match self.tokens.get(self.pos) {
+            Some((token, pos)) => CalcError::UnexpectedToken {
+                found: token.to_string(),
+                pos: *pos,
+            },
+            None => CalcError::UnexpectedEnd,
+        }
+    }
+
+    /// 语法树的深度同样受限：1 + 1 + ... 在解析时不递归，但求值和释放时会递归
+    fn node(expr: Expr, depth: usize, pos: usize) -> Result<(Expr, usize), CalcError> {
+        if depth > MAX_DEPTH {
+            return Err(CalcError::TooDeep { pos });
+        }
+        Ok((expr, depth))
+    }
+
+    /// 返回表达式和它的语法树深度
+    fn prefix(&mut self) -> Result<(Expr, usize), CalcError> {
+        let (token, pos) = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or(CalcError::UnexpectedEnd)?;
+        if self.depth > MAX_DEPTH {
+            return Err(CalcError::TooDeep { pos });
+        }
+        self.pos += 1;
+        match token {
+            Token::Num(n) => Ok((Expr::Num(n), 1)),
+            Token::Ident(name) => Ok((Expr::Var(name), 1)),
+            Token::Op(ref symbol) if symbol == "-" => {
+                let (operand, depth) = self.expr(u16::from(PREFIX_PRECEDENCE) * 2)?;
+                Self::node(Expr::Neg(Box::new(operand)), depth + 1, pos)
+            }
+            Token::LParen => {
+                let inner = self.expr(0)?;
+                if self.peek() != Some(&Token::RParen) {
+                    return Err(self.unexpected());
+                }
+                self.pos += 1;
+                Ok(inner)
+            }
+            _ => {
+                self.pos -= 1;
+                Err(self.unexpected())
+            }
+        }
+    }
+
+    fn expr(&mut self, min_bp: u16) -> Result<(Expr, usize), CalcError> {
+        self.depth += 1;
+        let (mut lhs, mut depth) = self.prefix()?;
+        while let Some((Token::Op(symbol), pos)) = self.tokens.get(self.pos) {
+            let op = self
+                .ops
+                .get(symbol)
+                .expect("tokenizer only emits known operators");
+            let (left_bp, right_bp) = op.binding_power();
+            if left_bp < min_bp {
+                break;
+            }
+            let (symbol, pos) = (symbol.clone(), *pos);
+            self.pos += 1;
+            let (rhs, rhs_depth) = self.expr(right_bp)?;
+            let binary = Expr::Binary {
+                op: symbol,
+                lhs: Box::new(lhs),
+                rhs: Box::new(rhs),
+            };
+            let (binary, binary_depth) = Self::node(binary, depth.max(rhs_depth) + 1, pos)?;
+            lhs = binary;
+            depth = binary_depth;
+        }
+        self.depth -= 1;
+        Ok((lhs, depth))
+    }
+}
+
+/// 解析整个表达式，多余的词法单元是错误
+pub fn parse(tokens: &[(Token, usize)], ops: &Operators) -> Result<Expr, CalcError> {
+    let mut parser = Parser {
+        tokens,
+        pos: 0,
+        ops,
+        depth: 0,
+    };
+    let (expr, _) = parser.expr(0)?;
+    if parser.pos < tokens.len() {
+        return Err(parser.unexpected());
+    }
+    Ok(expr)
+}
+
+pub fn evaluate(
+    expr: &Expr,
+    ops: &Operators,
+    vars: &BTreeMap<String, i32>,
+) -> Result<i32, CalcError> {
+    match expr {
+        Expr::Num(n) => Ok(*n),
+        Expr::Var(name) => vars
+            .get(name)
+            .copied()
+            .ok_or_else(|| CalcError::UnknownVariable(name.clone())),
+        Expr::Neg(e) => {
+            let x = evaluate(e, ops, vars)?;
+            overflow("-", 0, x, x.checked_neg())
+        }
+        Expr::Binary { op, lhs, rhs } => {
+            let lhs = evaluate(lhs, ops, vars)?;
+            let rhs = evaluate(rhs, ops, vars)?;
+            ops.apply(op, lhs, rhs)
+        }
+    }
+}
+
+/// 使用默认运算符、没有变量时对表达式求值，空表达式是错误
+pub fn eval(input: &str) -> Result<i32, CalcError> {
+    Calculator::new()
+        .eval_line(input)?
+        .ok_or(CalcError::UnexpectedEnd)
+}
+
+/// 保存变量的计算器，每次处理一行输入
+#[derive(Clone, Default)]
+pub struct Calculator {
+    ops: Operators,
+    vars: BTreeMap<String, i32>,
+}
+
+impl Calculator {
+    pub fn new() -> Self {
+        Calculator::default()
+    }
+
+    pub fn with_operators(ops: Operators) -> Self {
+        Calculator {
+            ops,
+            vars: BTreeMap::new(),
+        }
+    }
+
+    pub fn vars(&self) -> &BTreeMap<String, i32> {
+        &self.vars
+    }
+
+    /// 空行返回 None；`name = 表达式` 给变量赋值并返回它的值，出错时变量保持不变
+    pub fn eval_line(&mut self, line: &str) -> Result<Option<i32>, CalcError> {
+        let tokens = tokenize(line, &self.ops)?;
+        let (target, expr) = match tokens.as_slice() {
+            [] => return Ok(None),
+            [(Token::Ident(name), _), (Token::Assign, _), rest @ ..] => (Some(name), rest),
+            _ => (None, tokens.as_slice()),
+        };
+        let expr = parse(expr, &self.ops)?;
+        let value = evaluate(&expr, &self.ops, &self.vars)?;
+        if let Some(name) = target {
+            self.vars.insert(name.clone(), value);
+        }
+        Ok(Some(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        eval, evaluate, parse, tokenize, Assoc, CalcError, Calculator, Expr, Operator, Operators,
+        Token, MAX_DEPTH,
+    };
+    use std::collections::BTreeMap;
+
+    fn tree(input: &str) -> String {
+        let ops = Operators::default();
+        parse(&tokenize(input, &ops).unwrap(), &ops)
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn test_tokenize() {
+        let ops = Operators::default();
+        let tokens: Vec<Token> = tokenize("x1 = (12+_y)*3", &ops)
+            .unwrap()
+            .into_iter()
+            .map(|(t, _)| t)
+            .collect();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Ident("x1".to_string()),
+                Token::Assign,
+                Token::LParen,
+                Token::Num(12),
+                Token::Op("+".to_string()),
+                Token::Ident("_y".to_string()),
+                Token::RParen,
+                Token::Op("*".to_string()),
+                Token::Num(3),
+            ]
+        );
+        assert_eq!(
+            tokenize("1 + 2 $ 3", &ops),
+            Err(CalcError::UnexpectedChar { ch: '$', pos: 6 })
+        );
+        assert_eq!(
+            tokenize("2147483648", &ops),
+            Err(CalcError::NumberTooLarge { pos: 0 })
+        );
+    }
+
+    #[test]
+    fn test_precedence_and_assoc() {
+        assert_eq!(tree("1 + 2 * 3"), "(1 + (2 * 3))");
+        assert_eq!(tree("1 - 2 - 3"), "((1 - 2) - 3)");
+        assert_eq!(tree("2 ^ 3 ^ 2"), "(2 ^ (3 ^ 2))");
+        assert_eq!(tree("-2 ^ 2"), "(-(2 ^ 2))");
+        assert_eq!(tree("-2 * 3"), "((-2) * 3)");
+        assert_eq!(tree("(1 + 2) * -(3 - x)"), "((1 + 2) * (-(3 - x)))");
+        assert_eq!(tree("8 / 4 % 3 * 2"), "(((8 / 4) % 3) * 2)");
+        assert_eq!(tree("--1"), "(-(-1))");
+
+        assert_eq!(eval("1 + 2 * 3"), Ok(7));
+        assert_eq!(eval("2 ^ 3 ^ 2"), Ok(512));
+        assert_eq!(eval("-2 ^ 2"), Ok(-4));
+        assert_eq!(eval("(1 + 2) * (3 - 10) / 2"), Ok(-10));
+        assert_eq!(eval("-7 % 3"), Ok(-1));
+        assert_eq!(eval("((42))"), Ok(42));
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        let err = |input| eval(input).unwrap_err();
+        assert_eq!(err("1 +"), CalcError::UnexpectedEnd);
+        assert_eq!(err(""), CalcError::UnexpectedEnd);
+        assert_eq!(err("   "), CalcError::UnexpectedEnd);
+        assert_eq!(
+            err("()"),
+            CalcError::UnexpectedToken {
+                found: ")".to_string(),
+                pos: 1
+            }
+        );
+        assert_eq!(err("(1 + 2"), CalcError::UnexpectedEnd);
+        assert_eq!(
+            err("1 + 2)"),
+            CalcError::UnexpectedToken {
+                found: ")".to_string(),
+                pos: 5
+            }
+        );
+        assert_eq!(
+            err("* 3"),
+            CalcError::UnexpectedToken {
+                found: "*".to_string(),
+                pos: 0
+            }
+        );
+        assert_eq!(err("2 3").to_string(), "unexpected `3` at 2");
+        assert_eq!(err("1 = 2").to_string(), "unexpected `=` at 2");
+        assert_eq!(err("y + 1"), CalcError::UnknownVariable("y".to_string()));
+    }
+
+    #[test]
+    fn test_arithmetic_errors() {
+        assert_eq!(eval("1 / 0"), Err(CalcError::DivisionByZero));
+        assert_eq!(eval("1 % (2 - 2)"), Err(CalcError::DivisionByZero));
+        assert_eq!(eval("2 ^ -1"), Err(CalcError::NegativeExponent));
+        assert_eq!(
+            eval("2147483647 + 1"),
+            Err(CalcError::Overflow {
+                op: "+".to_string(),
+                lhs: i32::MAX,
+                rhs: 1
+            })
+        );
+        assert_eq!(eval("-2147483647 - 1"), Ok(i32::MIN));
+        // i32::MIN 取负、除以 -1 都会溢出
+        assert_eq!(
+            eval("-(-2147483647 - 1)"),
+            Err(CalcError::Overflow {
+                op: "-".to_string(),
+                lhs: 0,
+                rhs: i32::MIN
+            })
+        );
+        assert!(matches!(
+            eval("(-2147483647 - 1) / -1"),
+            Err(CalcError::Overflow { .. })
+        ));
+        assert!(matches!(
+            eval("(-2147483647 - 1) % -1"),
+            Err(CalcError::Overflow { .. })
+        ));
+        assert_eq!(
+            eval("2 ^ 31").unwrap_err().to_string(),
+            "overflow in 2 ^ 31"
+        );
+        assert_eq!(
+            eval("65536 * 32768 - 1"),
+            Err(CalcError::Overflow {
+                op: "*".to_string(),
+                lhs: 65536,
+                rhs: 32768
+            })
+        );
+    }
+
+    #[test]
+    fn test_unknown_operator() {
+        // 手工构造的语法树可以引用没有登记的运算符
+        let vars = BTreeMap::new();
+        let expr = Expr::Binary {
+            op: "?".to_string(),
+            lhs: Box::new(Expr::Num(1)),
+            rhs: Box::new(Expr::Num(2)),
+        };
+        let err = evaluate(&expr, &Operators::default(), &vars).unwrap_err();
+        assert_eq!(err, CalcError::UnknownOperator("?".to_string()));
+        assert_eq!(err.to_string(), "unknown operator `?`");
+        assert_eq!(
+            Operators::empty().apply("+", 1, 2),
+            Err(CalcError::UnknownOperator("+".to_string()))
+        );
+
+        // 取负不依赖 "-" 是否登记
+        let neg = Expr::Neg(Box::new(Expr::Num(5)));
+        assert_eq!(evaluate(&neg, &Operators::empty(), &vars), Ok(-5));
+    }
+
+    #[test]
+    fn test_nesting_limit() {
+        let nested =
+            |open: &str, close: &str, n: usize| format!("{}1{}", open.repeat(n), close.repeat(n));
+        assert_eq!(eval(&nested("(", ")", MAX_DEPTH - 1)), Ok(1));
+        assert_eq!(eval(&nested("-", "", MAX_DEPTH - 1)), Ok(-1));
+        assert_eq!(eval(&nested("", "+1", MAX_DEPTH - 1)), Ok(MAX_DEPTH as i32));
+        assert_eq!(
+            eval(&nested("(", ")", MAX_DEPTH + 1)),
+            Err(CalcError::TooDeep { pos: MAX_DEPTH })
+        );
+
+        // 足以耗尽栈空间的输入也只是返回错误
+        let too_deep = |input: &str| matches!(eval(input), Err(CalcError::TooDeep { .. }));
+        assert!(too_deep(&nested("(", ")", 200_000)));
+        assert!(too_deep(&nested("-", "", 300_000)));
+        assert!(too_deep(&nested("", "+1", 300_000)));
+        assert!(too_deep(&nested("", "^1", 300_000)));
+        assert_eq!(
+            eval(&nested("-(", ")", 200)).unwrap_err().to_string(),
+            "expression at 256 is nested too deeply"
+        );
+    }
+
+    #[test]
+    fn test_variables() {
+        let mut calc = Calculator::new();
+        assert_eq!(calc.eval_line("x = 6"), Ok(Some(6)));
+        assert_eq!(calc.eval_line("y = x * 7"), Ok(Some(42)));
+        assert_eq!(calc.eval_line("x = x + 1"), Ok(Some(7)));
+        assert_eq!(calc.eval_line("  "), Ok(None));
+        assert_eq!(calc.eval_line("y - x"), Ok(Some(35)));
+
+        // 求值失败时变量不变
+        assert_eq!(calc.eval_line("x = y / 0"), Err(CalcError::DivisionByZero));
+        assert_eq!(calc.vars().get("x"), Some(&7));
+        assert_eq!(
+            calc.vars().iter().collect::<Vec<_>>(),
+            vec![(&"x".to_string(), &7), (&"y".to_string(), &42)]
+        );
+    }
+
+    #[test]
+    fn test_custom_operators() {
+        fn max(a: i32, b: i32) -> i32 {
+            a.max(b)
+        }
+        fn shl(a: i32, b: i32) -> i32 {
+            a << b
+        }
+        let mut ops = Operators::default();
+        ops.register("|>", Operator::new(0, Assoc::Left, max, |_, _| Ok(())));
+        // 移位位数超出 0..=31 时 shl 会 panic，check 必须先拒绝
+        ops.register(
+            "<<",
+            Operator::new(1, Assoc::Left, shl, |a, b| match b {
+                0..=31 => Ok(()),
+                _ => Err(CalcError::Overflow {
+                    op: "<<".to_string(),
+                    lhs: a,
+                    rhs: b,
+                }),
+            }),
+        );
+        // 乘方换成 **，^ 不再是运算符
+        let pow = ops.unregister("^").unwrap();
+        ops.register("**", pow);
+        assert!(ops.unregister("^").is_none());
+
+        // 优先级取到 u8 的上限也没有问题
+        ops.register(
+            "@",
+            Operator::new(u8::MAX, Assoc::Right, max, |_, _| Ok(())),
+        );
+
+        let mut calc = Calculator::with_operators(ops.clone());
+        assert_eq!(calc.eval_line("2 ** 1 @ 3 @ -4"), Ok(Some(8)));
+        assert_eq!(calc.eval_line("1 << 4 |> 3 * 5"), Ok(Some(16)));
+        assert_eq!(calc.eval_line("2 ** 3 ** 2"), Ok(Some(512)));
+        // * 和 ** 共存时按最长匹配
+        assert_eq!(calc.eval_line("2 * 3 ** 2"), Ok(Some(18)));
+        assert!(calc.eval_line("1 << 40").is_err());
+        assert!(calc.eval_line("1 << -1").is_err());
+        // 运算自己报告错误时不需要单独的 check
+        ops.register(
+            ">>",
+            Operator::fallible(1, Assoc::Left, |a, b| match b {
+                0..=31 => Ok(a >> b),
+                _ => Err(CalcError::Overflow {
+                    op: ">>".to_string(),
+                    lhs: a,
+                    rhs: b,
+                }),
+            }),
+        );
+        assert_eq!(ops.apply(">>", 256, 4), Ok(16));
+        assert!(ops.apply(">>", 1, 32).is_err());
+        assert_eq!(
+            calc.eval_line("2 ^ 3"),
+            Err(CalcError::UnexpectedChar { ch: '^', pos: 2 })
+        );
+
+        let empty = Operators::empty();
+        assert_eq!(
+            tokenize("1 + 1", &empty),
+            Err(CalcError::UnexpectedChar { ch: '+', pos: 2 })
+        );
+    }
+
+    #[test]
+    fn test_operator_check() {
+        // check 拒绝了所有会让 func panic 的输入，func 只会收到合法输入
+        let mut ops = Operators::empty();
+        ops.register(
+            "//",
+            Operator::new(
+                2,
+                Assoc::Left,
+                |a, b| a / b,
+                |a, b| match (a, b) {
+                    (_, 0) => Err(CalcError::DivisionByZero),
+                    (i32::MIN, -1) => Err(CalcError::Overflow {
+                        op: "//".to_string(),
+                        lhs: a,
+                        rhs: b,
+                    }),
+                    _ => Ok(()),
+                },
+            ),
+        );
+        assert_eq!(ops.apply("//", 7, 2), Ok(3));
+        assert_eq!(ops.apply("//", 1, 0), Err(CalcError::DivisionByZero));
+        assert!(ops.apply("//", i32::MIN, -1).is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "attempt to divide by zero")]
+    fn test_operator_check_must_cover_func() {
+        // check 漏掉的输入会让 func panic
+        let mut ops = Operators::empty();
+        ops.register(
+            "//",
+            Operator::new(2, Assoc::Left, |a, b| a / b, |_, _| Ok(())),
+        );
+        let _ = ops.apply("//", 1, 0);
+    }
+}
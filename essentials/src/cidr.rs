@@ -0,0 +1,365 @@
+//! CIDR 网段，基于 ip::IpAddr
+//! 网段内部总是保存网络地址（主机位清零）和前缀长度，
+//! V4 和 V6 共用同一套基于 u128 的位运算，只是位宽不同。
+use crate::ip::{AddrParseError, IpAddr};
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Cidr {
+    network: IpAddr,
+    prefix: u8,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CidrError {
+    InvalidAddr(AddrParseError),
+    InvalidPrefix,
+}
+
+impl fmt::Display for CidrError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CidrError::InvalidAddr(e) => write!(f, "{}", e),
+            CidrError::InvalidPrefix => write!(f, "invalid prefix length"),
+        }
+    }
+}
+
+impl Error for CidrError {}
+
+impl From<AddrParseError> for CidrError {
+    fn from(e: AddrParseError) -> Self {
+        CidrError::InvalidAddr(e)
+    }
+}
+
+/// 低 host_bits 位全为 1 的掩码
+fn host_mask(host_bits: u8) -> u128 {
+    1u128
+        .checked_shl(host_bits as u32)
+        .map_or(!0, |b| b.wrapping_sub(1))
+}
+
+fn addr_from_bits(v4: bool, bits: u128) -> IpAddr {
+    if v4 {
+        IpAddr::from(bits as u32)
+    } else {
+        IpAddr::V6(bits)
+    }
+}
+
+impl Cidr {
+    /// 构造网段，addr 中的主机位会被清零，前缀超过地址位宽时返回错误
+    pub fn new(addr: IpAddr, prefix: u8) -> Result<Self, CidrError> {
+        if prefix > addr.bit_len() {
+            return Err(CidrError::InvalidPrefix);
+        }
+        let host_bits = addr.bit_len() - prefix;
+        let bits = addr.to_bits() & !host_mask(host_bits);
+        Ok(Cidr {
+            network: addr_from_bits(addr.is_ipv4(), bits),
+            prefix,
+        })
+    }
+
+    pub fn network(&self) -> IpAddr {
+        self.network
+    }
+
+    pub fn prefix_len(&self) -> u8 {
+        self.prefix
+    }
+
+    fn host_bits(&self) -> u8 {
+        self.network.bit_len() - self.prefix
+    }
+
+    fn first_bits(&self) -> u128 {
+        self.network.to_bits()
+    }
+
+    fn last_bits(&self) -> u128 {
+        self.first_bits() | host_mask(self.host_bits())
+    }
+
+    fn addr_at(&self, bits: u128) -> IpAddr {
+        addr_from_bits(self.network.is_ipv4(), bits)
+    }
+
+    /// 网段中的最后一个地址，对 V4 来说就是广播地址
+    pub fn last(&self) -> IpAddr {
+        self.addr_at(self.last_bits())
+    }
+
+    pub fn netmask(&self) -> IpAddr {
+        let full = host_mask(self.network.bit_len());
+        self.addr_at(full & !host_mask(self.host_bits()))
+    }
+
+    pub fn contains(&self, addr: IpAddr) -> bool {
+        addr.is_ipv4() == self.network.is_ipv4()
+            && (self.first_bits()..=self.last_bits()).contains(&addr.to_bits())
+    }
+
+    /// other 是否完全落在当前网段之内（包括相等）
+    pub fn contains_cidr(&self, other: &Cidr) -> bool {
+        other.prefix >= self.prefix && self.contains(other.network)
+    }
+
+    /// 将网段按 new_prefix 切分为等长子网
+    pub fn subnets(&self, new_prefix: u8) -> Result<Subnets, CidrError> {
+        if new_prefix < self.prefix || new_prefix > self.network.bit_len() {
+            return Err(CidrError::InvalidPrefix);
+        }
+        let step = 1u128.checked_shl((self.network.bit_len() - new_prefix) as u32);
+        Ok(Subnets {
+            v4: self.network.is_ipv4(),
+            prefix: new_prefix,
+            next: Some(self.first_bits()),
+            last: self.last_bits(),
+            step,
+        })
+    }
+
+    /// 前缀缩短一位的上级网段，/0 没有上级网段
+    pub fn supernet(&self) -> Option<Cidr> {
+        self.supernet_to(self.prefix.checked_sub(1)?)
+    }
+
+    pub fn supernet_to(&self, prefix: u8) -> Option<Cidr> {
+        if prefix > self.prefix {
+            return None;
+        }
+        Cidr::new(self.network, prefix).ok()
+    }
+
+    /// 可分配的主机地址。V4 的 /31 和 /32 没有网络地址和广播地址（RFC 3021），
+    /// 其余 V4 网段去掉首尾两个地址，V6 没有广播地址，返回全部地址
+    pub fn hosts(&self) -> Hosts {
+        let (mut first, mut last) = (self.first_bits(), self.last_bits());
+        if self.network.is_ipv4() && self.host_bits() >= 2 {
+            first += 1;
+            last -= 1;
+        }
+        Hosts {
+            v4: self.network.is_ipv4(),
+            next: Some(first),
+            last,
+        }
+    }
+
+    /// 两个网段是否是同一个上级网段的左右两半
+    fn is_sibling_of(&self, next: &Cidr) -> bool {
+        self.prefix == next.prefix
+            && self.prefix > 0
+            && self.network.is_ipv4() == next.network.is_ipv4()
+            && self.first_bits() & (1u128 << self.host_bits()) == 0
+            && self.last_bits().checked_add(1) == Some(next.first_bits())
+    }
+
+    /// 将一组网段聚合为覆盖完全相同地址集合的最少网段，结果按地址排序
+    pub fn aggregate(cidrs: &[Cidr]) -> Vec<Cidr> {
+        let mut sorted = cidrs.to_vec();
+        sorted.sort();
+        let mut stack: Vec<Cidr> = Vec::with_capacity(sorted.len());
+        for cidr in sorted {
+            // 排序后与之前网段重叠的只可能是栈顶，重叠的 CIDR 一定是包含关系
+            if stack.last().is_some_and(|top| top.contains_cidr(&cidr)) {
+                continue;
+            }
+            // 与栈顶互为左右两半时合并为上级网段，合并结果可能继续与新的栈顶合并
+            let mut cur = cidr;
+            while let Some(top) = stack.last() {
+                if !top.is_sibling_of(&cur) {
+                    break;
+                }
+                cur = stack.pop().unwrap().supernet().unwrap();
+            }
+            stack.push(cur);
+        }
+        stack
+    }
+}
+
+impl FromStr for Cidr {
+    type Err = CidrError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(2, '/');
+        let addr: IpAddr = parts.next().unwrap_or("").parse()?;
+        let prefix = parts.next().ok_or(CidrError::InvalidPrefix)?;
+        if prefix.is_empty() || !prefix.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(CidrError::InvalidPrefix);
+        }
+        let prefix = prefix.parse().map_err(|_| CidrError::InvalidPrefix)?;
+        Cidr::new(addr, prefix)
+    }
+}
+
+impl fmt::Display for Cidr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.network, self.prefix)
+    }
+}
+
+pub struct Subnets {
+    v4: bool,
+    prefix: u8,
+    next: Option<u128>,
+    last: u128,
+    // 步长超出 u128 时为 None，此时只有一个子网
+    step: Option<u128>,
+}
+
+impl Iterator for Subnets {
+    type Item = Cidr;
+
+    fn next(&mut self) -> Option<Cidr> {
+        let cur = self.next?;
+        self.next = self
+            .step
+            .and_then(|step| cur.checked_add(step))
+            .filter(|&n| n <= self.last);
+        Some(Cidr {
+            network: addr_from_bits(self.v4, cur),
+            prefix: self.prefix,
+        })
+    }
+}
+
+pub struct Hosts {
+    v4: bool,
+    next: Option<u128>,
+    last: u128,
+}
+
+impl Iterator for Hosts {
+    type Item = IpAddr;
+
+    fn next(&mut self) -> Option<IpAddr> {
+        let cur = self.next?;
+        self.next = cur.checked_add(1).filter(|&n| n <= self.last);
+        Some(addr_from_bits(self.v4, cur))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Cidr, CidrError};
+    use crate::ip::IpAddr;
+
+    fn cidr(s: &str) -> Cidr {
+        s.parse().unwrap()
+    }
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        let net = cidr("10.0.0.0/8");
+        assert_eq!(net.network(), IpAddr::V4(10, 0, 0, 0));
+        assert_eq!(net.prefix_len(), 8);
+        assert_eq!(net.to_string(), "10.0.0.0/8");
+        assert_eq!(net.netmask(), ip("255.0.0.0"));
+        assert_eq!(net.last(), ip("10.255.255.255"));
+        // 主机位被清零
+        assert_eq!(cidr("192.168.1.77/24").to_string(), "192.168.1.0/24");
+        assert_eq!(cidr("2001:db8::1/32").to_string(), "2001:db8::/32");
+        assert_eq!(cidr("::/0").last(), IpAddr::V6(!0));
+        assert_eq!(cidr("0.0.0.0/0").netmask(), ip("0.0.0.0"));
+
+        assert_eq!("10.0.0.0/33".parse::<Cidr>(), Err(CidrError::InvalidPrefix));
+        assert_eq!("10.0.0.0".parse::<Cidr>(), Err(CidrError::InvalidPrefix));
+        assert_eq!("10.0.0.0/+8".parse::<Cidr>(), Err(CidrError::InvalidPrefix));
+        assert!("10.0.0/8".parse::<Cidr>().is_err());
+    }
+
+    #[test]
+    fn test_contains() {
+        let net = cidr("172.16.0.0/12");
+        assert!(net.contains(ip("172.31.255.255")));
+        assert!(!net.contains(ip("172.32.0.0")));
+        assert!(!net.contains(ip("::ffff:172.16.0.1")));
+        assert!(net.contains_cidr(&cidr("172.20.0.0/16")));
+        assert!(!cidr("172.20.0.0/16").contains_cidr(&net));
+        assert!(cidr("::/0").contains(ip("ffff::1")));
+    }
+
+    #[test]
+    fn test_subnets_and_supernets() {
+        let subnets: Vec<_> = cidr("192.168.0.0/24").subnets(26).unwrap().collect();
+        assert_eq!(
+            subnets,
+            vec![
+                cidr("192.168.0.0/26"),
+                cidr("192.168.0.64/26"),
+                cidr("192.168.0.128/26"),
+                cidr("192.168.0.192/26"),
+            ]
+        );
+        assert_eq!(cidr("255.255.255.0/24").subnets(32).unwrap().count(), 256);
+        assert_eq!(cidr("::/0").subnets(0).unwrap().count(), 1);
+        assert_eq!(cidr("ffff::/16").subnets(17).unwrap().count(), 2);
+        assert!(cidr("10.0.0.0/8").subnets(7).is_err());
+
+        assert_eq!(cidr("10.1.0.0/16").supernet(), Some(cidr("10.0.0.0/15")));
+        assert_eq!(cidr("10.1.0.0/16").supernet_to(8), Some(cidr("10.0.0.0/8")));
+        assert_eq!(cidr("0.0.0.0/0").supernet(), None);
+    }
+
+    #[test]
+    fn test_hosts() {
+        let hosts: Vec<_> = cidr("10.0.0.0/30").hosts().collect();
+        assert_eq!(hosts, vec![ip("10.0.0.1"), ip("10.0.0.2")]);
+        assert_eq!(cidr("10.0.0.0/31").hosts().count(), 2);
+        assert_eq!(
+            cidr("10.0.0.7/32").hosts().collect::<Vec<_>>(),
+            vec![ip("10.0.0.7")]
+        );
+        assert_eq!(
+            cidr("255.255.255.0/24").hosts().last(),
+            Some(ip("255.255.255.254"))
+        );
+        assert_eq!(
+            cidr("ffff:ffff:ffff:ffff:ffff:ffff:ffff:fffc/126")
+                .hosts()
+                .count(),
+            4
+        );
+    }
+
+    #[test]
+    fn test_aggregate() {
+        let input: Vec<Cidr> = [
+            "10.0.1.0/24",
+            "10.0.0.0/24",
+            "10.0.2.0/23",
+            "10.0.3.128/25",
+            "192.168.0.0/24",
+            "192.168.2.0/24",
+            "2001:db8::/33",
+            "2001:db8:8000::/33",
+        ]
+        .iter()
+        .map(|s| cidr(s))
+        .collect();
+        assert_eq!(
+            Cidr::aggregate(&input),
+            vec![
+                cidr("10.0.0.0/22"),
+                cidr("192.168.0.0/24"),
+                cidr("192.168.2.0/24"),
+                cidr("2001:db8::/32"),
+            ]
+        );
+        assert_eq!(
+            Cidr::aggregate(&[cidr("0.0.0.0/1"), cidr("128.0.0.0/1")]),
+            vec![cidr("0.0.0.0/0")]
+        );
+        assert!(Cidr::aggregate(&[]).is_empty());
+    }
+}
@@ -0,0 +1,477 @@
+//! 颜色空间转换
+//! Color 是 sRGB 空间中 0..=255 的三个分量，超出范围的分量在转换时会被截断。
+//! Hsl/Hsv 的色相 h 取值 [0, 360)，其余分量取值 [0, 1]；Lab 使用 D65 白点。
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+// Tuple-Like struct, file no named
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Color(pub i32, pub i32, pub i32);
+
+// c-like enum
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Color1 {
+    Red = 0xff0000,
+    Green = 0x00ff00,
+    Blue = 0x0000ff,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hsl {
+    pub h: f64,
+    pub s: f64,
+    pub l: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hsv {
+    pub h: f64,
+    pub s: f64,
+    pub v: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Lab {
+    pub l: f64,
+    pub a: f64,
+    pub b: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseColorError;
+
+impl fmt::Display for ParseColorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "expected a color like #rrggbb")
+    }
+}
+
+impl Error for ParseColorError {}
+
+fn clamp_channel(c: i32) -> u8 {
+    c.clamp(0, 255) as u8
+}
+
+fn to_channel(unit: f64) -> i32 {
+    (unit.clamp(0.0, 1.0) * 255.0).round() as i32
+}
+
+// sRGB 的 gamma 编码与线性光之间的转换
+fn to_linear(c: f64) -> f64 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn from_linear(c: f64) -> f64 {
+    if c <= 0.003_130_8 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+// D65 白点
+const WHITE: (f64, f64, f64) = (0.950_47, 1.0, 1.088_83);
+
+impl Color {
+    pub fn r(&self) -> u8 {
+        clamp_channel(self.0)
+    }
+
+    pub fn g(&self) -> u8 {
+        clamp_channel(self.1)
+    }
+
+    pub fn b(&self) -> u8 {
+        clamp_channel(self.2)
+    }
+
+    /// 0xRRGGBB 打包形式
+    pub fn from_hex(hex: u32) -> Self {
+        let [_, r, g, b] = hex.to_be_bytes();
+        Color(r as i32, g as i32, b as i32)
+    }
+
+    pub fn to_hex(self) -> u32 {
+        u32::from_be_bytes([0, self.r(), self.g(), self.b()])
+    }
+
+    fn unit(self) -> (f64, f64, f64) {
+        (
+            self.r() as f64 / 255.0,
+            self.g() as f64 / 255.0,
+            self.b() as f64 / 255.0,
+        )
+    }
+
+    fn from_unit(r: f64, g: f64, b: f64) -> Self {
+        Color(to_channel(r), to_channel(g), to_channel(b))
+    }
+
+    /// 最大分量、最小分量和色相，HSL 与 HSV 共用
+    fn hue_chroma(self) -> (f64, f64, f64) {
+        let (r, g, b) = self.unit();
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let d = max - min;
+        let h = if d == 0.0 {
+            0.0
+        } else if max == r {
+            60.0 * ((g - b) / d).rem_euclid(6.0)
+        } else if max == g {
+            60.0 * ((b - r) / d + 2.0)
+        } else {
+            60.0 * ((r - g) / d + 4.0)
+        };
+        (max, min, h)
+    }
+
+    /// 由色相、色度和明度偏移量还原 RGB
+    fn from_hue_chroma(h: f64, c: f64, m: f64) -> Self {
+        let h = h.rem_euclid(360.0) / 60.0;
+        let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+        let (r, g, b) = match h as u32 {
+            0 => (c, x, 0.0),
+            1 => (x, c, 0.0),
+            2 => (0.0, c, x),
+            3 => (0.0, x, c),
+            4 => (x, 0.0, c),
+            _ => (c, 0.0, x),
+        };
+        Color::from_unit(r + m, g + m, b + m)
+    }
+
+    pub fn to_hsl(self) -> Hsl {
+        let (max, min, h) = self.hue_chroma();
+        let l = (max + min) / 2.0;
+        let d = max - min;
+        let s = if d == 0.0 {
+            0.0
+        } else {
+            d / (1.0 - (2.0 * l - 1.0).abs())
+        };
+        Hsl { h, s, l }
+    }
+
+    pub fn from_hsl(hsl: Hsl) -> Self {
+        let c = (1.0 - (2.0 * hsl.l - 1.0).abs()) * hsl.s;
+        Color::from_hue_chroma(hsl.h, c, hsl.l - c / 2.0)
+    }
+
+    pub fn to_hsv(self) -> Hsv {
+        let (max, min, h) = self.hue_chroma();
+        let s = if max == 0.0 { 0.0 } else { (max - min) / max };
+        Hsv { h, s, v: max }
+    }
+
+    pub fn from_hsv(hsv: Hsv) -> Self {
+        let c = hsv.v * hsv.s;
+        Color::from_hue_chroma(hsv.h, c, hsv.v - c)
+    }
+
+    /// sRGB -> 线性 RGB -> XYZ -> CIE L*a*b*
+    pub fn to_lab(self) -> Lab {
+        let (r, g, b) = self.unit();
+        let (r, g, b) = (to_linear(r), to_linear(g), to_linear(b));
+        let x = 0.412_456_4 * r + 0.357_576_1 * g + 0.180_437_5 * b;
+        let y = 0.212_672_9 * r + 0.715_152_2 * g + 0.072_175_0 * b;
+        let z = 0.019_333_9 * r + 0.119_192_0 * g + 0.950_304_1 * b;
+
+        let f = |t: f64| {
+            if t > 216.0 / 24389.0 {
+                t.cbrt()
+            } else {
+                (24389.0 / 27.0 * t + 16.0) / 116.0
+            }
+        };
+        let (fx, fy, fz) = (f(x / WHITE.0), f(y / WHITE.1), f(z / WHITE.2));
+        Lab {
+            l: 116.0 * fy - 16.0,
+            a: 500.0 * (fx - fy),
+            b: 200.0 * (fy - fz),
+        }
+    }
+
+    pub fn from_lab(lab: Lab) -> Self {
+        let fy = (lab.l + 16.0) / 116.0;
+        let fx = fy + lab.a / 500.0;
+        let fz = fy - lab.b / 200.0;
+        let f_inv = |t: f64| {
+            if t.powi(3) > 216.0 / 24389.0 {
+                t.powi(3)
+            } else {
+                (116.0 * t - 16.0) * 27.0 / 24389.0
+            }
+        };
+        let (x, y, z) = (
+            f_inv(fx) * WHITE.0,
+            f_inv(fy) * WHITE.1,
+            f_inv(fz) * WHITE.2,
+        );
+
+        let r = 3.240_454_2 * x - 1.537_138_5 * y - 0.498_531_4 * z;
+        let g = -0.969_266_0 * x + 1.876_010_8 * y + 0.041_556_0 * z;
+        let b = 0.055_643_4 * x - 0.204_025_9 * y + 1.057_225_2 * z;
+        Color::from_unit(from_linear(r), from_linear(g), from_linear(b))
+    }
+
+    /// 将不透明度为 alpha 的 fg 叠加到当前颜色之上（source-over）
+    pub fn blend(self, fg: Color, alpha: f64) -> Color {
+        let alpha = alpha.clamp(0.0, 1.0);
+        let (br, bg, bb) = self.unit();
+        let (fr, fg, fb) = fg.unit();
+        let mix = |f: f64, b: f64| f * alpha + b * (1.0 - alpha);
+        Color::from_unit(mix(fr, br), mix(fg, bg), mix(fb, bb))
+    }
+
+    /// CIEDE2000 色差，数值约为 1 时人眼刚好能分辨
+    pub fn delta_e(self, other: Color) -> f64 {
+        delta_e_2000(self.to_lab(), other.to_lab())
+    }
+}
+
+pub fn delta_e_2000(c1: Lab, c2: Lab) -> f64 {
+    let pow7 = |x: f64| x.powi(7);
+    let c_bar = (c1.a.hypot(c1.b) + c2.a.hypot(c2.b)) / 2.0;
+    let g = 0.5 * (1.0 - (pow7(c_bar) / (pow7(c_bar) + pow7(25.0))).sqrt());
+    let (a1, a2) = (c1.a * (1.0 + g), c2.a * (1.0 + g));
+    let (cp1, cp2) = (a1.hypot(c1.b), a2.hypot(c2.b));
+    let hue = |b: f64, a: f64| {
+        if a == 0.0 && b == 0.0 {
+            0.0
+        } else {
+            b.atan2(a).to_degrees().rem_euclid(360.0)
+        }
+    };
+    let (hp1, hp2) = (hue(c1.b, a1), hue(c2.b, a2));
+
+    let dl = c2.l - c1.l;
+    let dc = cp2 - cp1;
+    let dh = if cp1 * cp2 == 0.0 {
+        0.0
+    } else if (hp2 - hp1).abs() <= 180.0 {
+        hp2 - hp1
+    } else if hp2 - hp1 > 180.0 {
+        hp2 - hp1 - 360.0
+    } else {
+        hp2 - hp1 + 360.0
+    };
+    let dh = 2.0 * (cp1 * cp2).sqrt() * (dh / 2.0).to_radians().sin();
+
+    let l_bar = (c1.l + c2.l) / 2.0;
+    let cp_bar = (cp1 + cp2) / 2.0;
+    let hp_bar = if cp1 * cp2 == 0.0 {
+        hp1 + hp2
+    } else if (hp1 - hp2).abs() <= 180.0 {
+        (hp1 + hp2) / 2.0
+    } else if hp1 + hp2 < 360.0 {
+        (hp1 + hp2 + 360.0) / 2.0
+    } else {
+        (hp1 + hp2 - 360.0) / 2.0
+    };
+
+    let t = 1.0 - 0.17 * (hp_bar - 30.0).to_radians().cos()
+        + 0.24 * (2.0 * hp_bar).to_radians().cos()
+        + 0.32 * (3.0 * hp_bar + 6.0).to_radians().cos()
+        - 0.20 * (4.0 * hp_bar - 63.0).to_radians().cos();
+    let d_theta = 30.0 * (-((hp_bar - 275.0) / 25.0).powi(2)).exp();
+    let rc = 2.0 * (pow7(cp_bar) / (pow7(cp_bar) + pow7(25.0))).sqrt();
+    let sl = 1.0 + 0.015 * (l_bar - 50.0).powi(2) / (20.0 + (l_bar - 50.0).powi(2)).sqrt();
+    let sc = 1.0 + 0.045 * cp_bar;
+    let sh = 1.0 + 0.015 * cp_bar * t;
+    let rt = -(2.0 * d_theta).to_radians().sin() * rc;
+
+    ((dl / sl).powi(2) + (dc / sc).powi(2) + (dh / sh).powi(2) + rt * (dc / sc) * (dh / sh)).sqrt()
+}
+
+impl From<Color1> for Color {
+    fn from(c: Color1) -> Self {
+        Color::from_hex(c as u32)
+    }
+}
+
+/// 只有和 Color1 判别值完全相同的颜色才能转换回去
+impl TryFrom<Color> for Color1 {
+    type Error = Color;
+
+    fn try_from(c: Color) -> Result<Self, Self::Error> {
+        [Color1::Red, Color1::Green, Color1::Blue]
+            .iter()
+            .copied()
+            .find(|&c1| Color::from(c1) == c)
+            .ok_or(c)
+    }
+}
+
+/// 支持 #rrggbb 和简写的 #rgb
+impl FromStr for Color {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = s.strip_prefix('#').ok_or(ParseColorError)?;
+        if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(ParseColorError);
+        }
+        let packed = u32::from_str_radix(hex, 16).map_err(|_| ParseColorError)?;
+        match hex.len() {
+            6 => Ok(Color::from_hex(packed)),
+            3 => {
+                let nibble = |shift: u32| ((packed >> shift) & 0xf) as i32 * 0x11;
+                Ok(Color(nibble(8), nibble(4), nibble(0)))
+            }
+            _ => Err(ParseColorError),
+        }
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{:06x}", self.to_hex())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{delta_e_2000, Color, Color1, Hsl, Hsv, Lab};
+    use std::convert::TryFrom;
+
+    fn approx(a: f64, b: f64, eps: f64) -> bool {
+        (a - b).abs() < eps
+    }
+
+    #[test]
+    fn test_hex_and_string() {
+        assert_eq!(Color::from_hex(0x0a141e), Color(10, 20, 30));
+        assert_eq!(Color(10, 20, 30).to_hex(), 0x0a141e);
+        assert_eq!(Color(300, -5, 30).to_hex(), 0xff001e);
+        assert_eq!(Color(10, 20, 30).to_string(), "#0a141e");
+        assert_eq!("#0A141e".parse(), Ok(Color(10, 20, 30)));
+        assert_eq!("#fa0".parse(), Ok(Color(255, 170, 0)));
+        for bad in &["0a141e", "#0a141", "#0a141eff", "#+a141e", "#xyzxyz", "#"] {
+            assert!(bad.parse::<Color>().is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_color1_round_trip() {
+        for &c in &[Color1::Red, Color1::Green, Color1::Blue] {
+            let color = Color::from(c);
+            assert_eq!(color.to_hex(), c as u32);
+            assert_eq!(Color1::try_from(color), Ok(c));
+        }
+        assert_eq!(Color::from(Color1::Green), Color(0, 255, 0));
+        assert_eq!(Color1::try_from(Color(255, 0, 1)), Err(Color(255, 0, 1)));
+        // 超出范围的分量截断后等于 0xff0000，但并不是同一个颜色
+        assert!(Color1::try_from(Color(256, 0, 0)).is_err());
+    }
+
+    #[test]
+    fn test_hsl_hsv() {
+        let hsl = Color(255, 0, 0).to_hsl();
+        assert_eq!(
+            hsl,
+            Hsl {
+                h: 0.0,
+                s: 1.0,
+                l: 0.5
+            }
+        );
+        let hsv = Color(0, 0, 255).to_hsv();
+        assert_eq!(
+            hsv,
+            Hsv {
+                h: 240.0,
+                s: 1.0,
+                v: 1.0
+            }
+        );
+        assert_eq!(Color(128, 128, 128).to_hsl().s, 0.0);
+
+        let hsl = Color(10, 20, 30).to_hsl();
+        assert!(approx(hsl.h, 210.0, 1e-9));
+        assert_eq!(
+            Color::from_hsl(Hsl {
+                h: 120.0,
+                s: 1.0,
+                l: 0.25
+            }),
+            Color(0, 128, 0)
+        );
+        assert_eq!(
+            Color::from_hsv(Hsv {
+                h: 60.0,
+                s: 1.0,
+                v: 1.0
+            }),
+            Color(255, 255, 0)
+        );
+
+        // 所有颜色经过 HSL/HSV 往返都不会有损失
+        for hex in (0..0x100_0000).step_by(0x1f3d) {
+            let c = Color::from_hex(hex);
+            assert_eq!(Color::from_hsl(c.to_hsl()), c);
+            assert_eq!(Color::from_hsv(c.to_hsv()), c);
+            assert_eq!(Color::from_lab(c.to_lab()), c);
+        }
+    }
+
+    #[test]
+    fn test_lab() {
+        let white = Color(255, 255, 255).to_lab();
+        assert!(approx(white.l, 100.0, 1e-3));
+        assert!(approx(white.a, 0.0, 1e-3) && approx(white.b, 0.0, 1e-3));
+        let red = Color(255, 0, 0).to_lab();
+        assert!(approx(red.l, 53.24, 0.01));
+        assert!(approx(red.a, 80.09, 0.01));
+        assert!(approx(red.b, 67.20, 0.01));
+        assert_eq!(Color(0, 0, 0).to_lab().l, 0.0);
+    }
+
+    #[test]
+    fn test_blend() {
+        let bg = Color(0, 0, 0);
+        assert_eq!(bg.blend(Color(255, 255, 255), 0.5), Color(128, 128, 128));
+        assert_eq!(bg.blend(Color(200, 100, 50), 1.0), Color(200, 100, 50));
+        assert_eq!(bg.blend(Color(200, 100, 50), 0.0), bg);
+        assert_eq!(bg.blend(Color(200, 100, 50), 7.0), Color(200, 100, 50));
+    }
+
+    #[test]
+    fn test_delta_e() {
+        // Sharma, Wu, Dalal (2005) 给出的 CIEDE2000 测试数据
+        let cases = [
+            ((50.0, 2.6772, -79.7751), (50.0, 0.0, -82.7485), 2.0425),
+            ((50.0, 3.1571, -77.2803), (50.0, 0.0, -82.7485), 2.8615),
+            ((50.0, 2.8361, -74.0200), (50.0, 0.0, -82.7485), 3.4412),
+            ((50.0, 0.0, 0.0), (50.0, -1.0, 2.0), 2.3669),
+            ((50.0, 2.5, 0.0), (73.0, 25.0, -18.0), 27.1492),
+            (
+                (60.2574, -34.0099, 36.2677),
+                (60.4626, -34.1751, 39.4387),
+                1.2644,
+            ),
+        ];
+        for &((l1, a1, b1), (l2, a2, b2), expected) in cases.iter() {
+            let c1 = Lab {
+                l: l1,
+                a: a1,
+                b: b1,
+            };
+            let c2 = Lab {
+                l: l2,
+                a: a2,
+                b: b2,
+            };
+            assert!(approx(delta_e_2000(c1, c2), expected, 1e-4));
+            assert!(approx(delta_e_2000(c2, c1), expected, 1e-4));
+        }
+        let c = Color(10, 20, 30);
+        assert_eq!(c.delta_e(c), 0.0);
+        assert!(Color(255, 0, 0).delta_e(Color(0, 255, 0)) > 50.0);
+    }
+}
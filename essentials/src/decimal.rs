@@ -0,0 +1,499 @@
+//! 定点十进制数
+//! 二进制浮点数无法精确表示 0.1 这样的十进制小数，金额计算需要十进制的定点数。
+//! Decimal<SCALE> 内部是一个 i128，表示 value * 10^SCALE，小数位数 SCALE 是类型的一部分，
+//! 不同精度的金额不能直接相加，需要显式地 rescale。
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+use std::str::FromStr;
+
+/// 舍入方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rounding {
+    /// 四舍六入五成双（银行家舍入），正好一半时舍入到偶数
+    HalfEven,
+    /// 四舍五入，正好一半时远离零
+    HalfUp,
+    /// 直接截断，向零舍入
+    Truncate,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseDecimalError {
+    /// 不是 [+-]数字[.数字] 的形式
+    Invalid,
+    /// 小数位数超过 SCALE，解析会丢失精度
+    TooPrecise,
+    Overflow,
+}
+
+impl fmt::Display for ParseDecimalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseDecimalError::Invalid => write!(f, "invalid decimal literal"),
+            ParseDecimalError::TooPrecise => write!(f, "too many fractional digits"),
+            ParseDecimalError::Overflow => write!(f, "decimal out of range"),
+        }
+    }
+}
+
+impl Error for ParseDecimalError {}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct Decimal<const SCALE: u32>(i128);
+
+/// 根据被舍弃的余数决定商的绝对值是否要加一
+fn round_quotient(q: u128, rem: u128, divisor: u128, mode: Rounding) -> Option<u128> {
+    let half = rem.cmp(&(divisor - rem));
+    let up = match mode {
+        Rounding::Truncate => false,
+        Rounding::HalfUp => half != std::cmp::Ordering::Less,
+        Rounding::HalfEven => {
+            half == std::cmp::Ordering::Greater || (half == std::cmp::Ordering::Equal && q % 2 == 1)
+        }
+    };
+    if up {
+        q.checked_add(1)
+    } else {
+        Some(q)
+    }
+}
+
+/// 128 位乘 128 位得到 256 位的 (高位, 低位)
+fn widening_mul(a: u128, b: u128) -> (u128, u128) {
+    const MASK: u128 = u64::MAX as u128;
+    let (a1, a0) = (a >> 64, a & MASK);
+    let (b1, b0) = (b >> 64, b & MASK);
+    let (p00, p01, p10, p11) = (a0 * b0, a0 * b1, a1 * b0, a1 * b1);
+    let mid = (p00 >> 64) + (p01 & MASK) + (p10 & MASK);
+    let lo = (p00 & MASK) | (mid << 64);
+    let hi = p11 + (p01 >> 64) + (p10 >> 64) + (mid >> 64);
+    (hi, lo)
+}
+
+/// 按 mode 舍入的 a * b / d，中间结果用 256 位，只有最终结果超出 i128 时才返回 None
+fn mul_div(a: i128, b: i128, d: i128, mode: Rounding) -> Option<i128> {
+    let negative = ((a < 0) != (b < 0)) != (d < 0);
+    let (hi, lo) = widening_mul(a.unsigned_abs(), b.unsigned_abs());
+    let d = d.unsigned_abs();
+    // 商的高 128 位不为零时必然溢出
+    if hi >= d {
+        return None;
+    }
+    // 逐位的长除法，每一步余数都小于 d
+    let (mut q, mut rem) = (0u128, hi);
+    for i in (0..128).rev() {
+        let carry = rem >> 127;
+        rem = (rem << 1) | ((lo >> i) & 1);
+        q <<= 1;
+        if carry == 1 || rem >= d {
+            rem = rem.wrapping_sub(d);
+            q |= 1;
+        }
+    }
+    let q = round_quotient(q, rem, d, mode)?;
+    if negative {
+        0i128.checked_sub_unsigned(q)
+    } else {
+        i128::try_from(q).ok()
+    }
+}
+
+impl<const SCALE: u32> Decimal<SCALE> {
+    /// 10^SCALE，SCALE 超过 38 时无法通过编译
+    const FACTOR: i128 = 10i128.pow(SCALE);
+
+    pub const ZERO: Self = Decimal(0);
+    pub const ONE: Self = Decimal(Self::FACTOR);
+    pub const MAX: Self = Decimal(i128::MAX);
+    pub const MIN: Self = Decimal(i128::MIN);
+
+    /// 以最小单位 10^-SCALE 计数的原始值，比如 Decimal::<2>::from_raw(150) 是 1.50
+    pub const fn from_raw(raw: i128) -> Self {
+        Decimal(raw)
+    }
+
+    pub const fn raw(self) -> i128 {
+        self.0
+    }
+
+    pub fn from_int(n: i128) -> Option<Self> {
+        n.checked_mul(Self::FACTOR).map(Decimal)
+    }
+
+    /// 整数部分，向零截断
+    pub fn trunc(self) -> i128 {
+        self.0 / Self::FACTOR
+    }
+
+    pub fn is_negative(self) -> bool {
+        self.0 < 0
+    }
+
+    /// MIN 的绝对值超出范围时返回 None
+    pub fn checked_abs(self) -> Option<Self> {
+        self.0.checked_abs().map(Decimal)
+    }
+
+    /// self 为 MIN 时 panic，见 checked_abs
+    pub fn abs(self) -> Self {
+        self.checked_abs()
+            .expect("attempt to take abs with overflow")
+    }
+
+    /// 保留 digits 位小数，digits 不小于 SCALE 时原样返回；
+    /// 只有非常接近 MAX / MIN 时进位才会超出范围，此时返回 None
+    pub fn checked_round(self, digits: u32, mode: Rounding) -> Option<Self> {
+        if digits >= SCALE {
+            return Some(self);
+        }
+        let unit = 10i128.pow(SCALE - digits);
+        mul_div(self.0, 1, unit, mode)?
+            .checked_mul(unit)
+            .map(Decimal)
+    }
+
+    /// 进位超出范围时 panic，见 checked_round
+    pub fn round(self, digits: u32, mode: Rounding) -> Self {
+        self.checked_round(digits, mode)
+            .expect("attempt to round with overflow")
+    }
+
+    /// 转换到另一个精度，降低精度时按 mode 舍入，超出范围时返回 None
+    pub fn rescale<const TO: u32>(self, mode: Rounding) -> Option<Decimal<TO>> {
+        if TO >= SCALE {
+            self.0
+                .checked_mul(10i128.checked_pow(TO - SCALE)?)
+                .map(Decimal)
+        } else {
+            mul_div(self.0, 1, 10i128.pow(SCALE - TO), mode).map(Decimal)
+        }
+    }
+
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        self.0.checked_add(rhs.0).map(Decimal)
+    }
+
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        self.0.checked_sub(rhs.0).map(Decimal)
+    }
+
+    /// 乘积的小数位数是 2 * SCALE，按 mode 舍入回 SCALE 位
+    pub fn checked_mul(self, rhs: Self, mode: Rounding) -> Option<Self> {
+        mul_div(self.0, rhs.0, Self::FACTOR, mode).map(Decimal)
+    }
+
+    /// 除数为零时返回 None
+    pub fn checked_div(self, rhs: Self, mode: Rounding) -> Option<Self> {
+        if rhs.0 == 0 {
+            return None;
+        }
+        mul_div(self.0, Self::FACTOR, rhs.0, mode).map(Decimal)
+    }
+
+    pub fn saturating_add(self, rhs: Self) -> Self {
+        Decimal(self.0.saturating_add(rhs.0))
+    }
+
+    pub fn saturating_sub(self, rhs: Self) -> Self {
+        Decimal(self.0.saturating_sub(rhs.0))
+    }
+
+    pub fn saturating_mul(self, rhs: Self, mode: Rounding) -> Self {
+        self.checked_mul(rhs, mode)
+            .unwrap_or(if (self.0 < 0) != (rhs.0 < 0) {
+                Self::MIN
+            } else {
+                Self::MAX
+            })
+    }
+
+    /// 除数为零时 panic
+    pub fn saturating_div(self, rhs: Self, mode: Rounding) -> Self {
+        assert!(rhs.0 != 0, "attempt to divide by zero");
+        self.checked_div(rhs, mode)
+            .unwrap_or(if (self.0 < 0) != (rhs.0 < 0) {
+                Self::MIN
+            } else {
+                Self::MAX
+            })
+    }
+}
+
+impl<const SCALE: u32> Add for Decimal<SCALE> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        self.checked_add(rhs).expect("attempt to add with overflow")
+    }
+}
+
+impl<const SCALE: u32> Sub for Decimal<SCALE> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        self.checked_sub(rhs)
+            .expect("attempt to subtract with overflow")
+    }
+}
+
+/// 运算符版本的乘除使用银行家舍入，需要其他舍入方式时使用 checked_mul / checked_div
+impl<const SCALE: u32> Mul for Decimal<SCALE> {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        self.checked_mul(rhs, Rounding::HalfEven)
+            .expect("attempt to multiply with overflow")
+    }
+}
+
+impl<const SCALE: u32> Div for Decimal<SCALE> {
+    type Output = Self;
+
+    fn div(self, rhs: Self) -> Self {
+        assert!(rhs.0 != 0, "attempt to divide by zero");
+        self.checked_div(rhs, Rounding::HalfEven)
+            .expect("attempt to divide with overflow")
+    }
+}
+
+impl<const SCALE: u32> Neg for Decimal<SCALE> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Decimal(
+            self.0
+                .checked_neg()
+                .expect("attempt to negate with overflow"),
+        )
+    }
+}
+
+impl<const SCALE: u32> FromStr for Decimal<SCALE> {
+    type Err = ParseDecimalError;
+
+    /// 小数部分可以少于 SCALE 位，但不能多于 SCALE 位，不做任何隐式舍入
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (negative, body) = match s.as_bytes().first() {
+            Some(b'-') => (true, &s[1..]),
+            Some(b'+') => (false, &s[1..]),
+            _ => (false, s),
+        };
+        let (int, frac) = match body.find('.') {
+            Some(dot) => (&body[..dot], &body[dot + 1..]),
+            None => (body, ""),
+        };
+        let all_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+        if int.is_empty() || !all_digits(int) || !all_digits(frac) || body.ends_with('.') {
+            return Err(ParseDecimalError::Invalid);
+        }
+        if frac.len() > SCALE as usize {
+            return Err(ParseDecimalError::TooPrecise);
+        }
+
+        // 按负数累加，这样 MIN 也能解析
+        let mut raw = 0i128;
+        let padding = std::iter::repeat_n(b'0', SCALE as usize - frac.len());
+        for b in int.bytes().chain(frac.bytes()).chain(padding) {
+            raw = raw
+                .checked_mul(10)
+                .and_then(|r| r.checked_sub((b - b'0') as i128))
+                .ok_or(ParseDecimalError::Overflow)?;
+        }
+        if !negative {
+            raw = raw.checked_neg().ok_or(ParseDecimalError::Overflow)?;
+        }
+        Ok(Decimal(raw))
+    }
+}
+
+/// 总是输出 SCALE 位小数，所以 to_string 之后再 parse 得到完全相同的值
+impl<const SCALE: u32> fmt::Display for Decimal<SCALE> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let abs = self.0.unsigned_abs();
+        let factor = Self::FACTOR as u128;
+        let digits = if SCALE == 0 {
+            abs.to_string()
+        } else {
+            format!(
+                "{}.{:0width$}",
+                abs / factor,
+                abs % factor,
+                width = SCALE as usize
+            )
+        };
+        f.pad_integral(self.0 >= 0, "", &digits)
+    }
+}
+
+impl<const SCALE: u32> fmt::Debug for Decimal<SCALE> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Decimal, ParseDecimalError, Rounding};
+    use crate::test_util::Rng;
+
+    type Money = Decimal<2>;
+
+    fn d2(s: &str) -> Money {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        assert_eq!(d2("1.5").raw(), 150);
+        assert_eq!(d2("-0.07").raw(), -7);
+        assert_eq!(d2("+12").to_string(), "12.00");
+        assert_eq!(d2("-0.5").to_string(), "-0.50");
+        assert_eq!(d2("-0").to_string(), "0.00");
+        assert_eq!(format!("{:>8}", d2("3.1")), "    3.10");
+        assert_eq!("0.001".parse::<Money>(), Err(ParseDecimalError::TooPrecise));
+        for bad in &["", "-", ".5", "5.", "1.2.3", "1e5", "1,000", " 1"] {
+            assert_eq!(
+                bad.parse::<Money>(),
+                Err(ParseDecimalError::Invalid),
+                "{}",
+                bad
+            );
+        }
+        assert_eq!(Decimal::<0>::from_raw(42).to_string(), "42");
+        assert_eq!(
+            Decimal::<38>::ONE.to_string(),
+            format!("1.{}", "0".repeat(38))
+        );
+
+        let max = Money::MAX.to_string();
+        let min = Money::MIN.to_string();
+        assert_eq!(max.parse::<Money>(), Ok(Money::MAX));
+        assert_eq!(min.parse::<Money>(), Ok(Money::MIN));
+        assert_eq!(
+            format!("{}9", max).parse::<Money>(),
+            Err(ParseDecimalError::TooPrecise)
+        );
+        assert_eq!(
+            format!("1{}", max).parse::<Money>(),
+            Err(ParseDecimalError::Overflow)
+        );
+    }
+
+    #[test]
+    fn test_string_round_trip() {
+        let mut rng = Rng(0x0dec_13a1);
+        for _ in 0..1000 {
+            let raw = ((rng.next() as u128) << 64 | rng.next() as u128) as i128;
+            let raw = raw >> (rng.next() % 128);
+            let x = Decimal::<6>::from_raw(raw);
+            assert_eq!(x.to_string().parse(), Ok(x));
+        }
+    }
+
+    #[test]
+    fn test_exact_arithmetic() {
+        // 0.1 + 0.2 在 f64 下不等于 0.3，十进制定点数是精确的
+        assert_eq!(d2("0.10") + d2("0.20"), d2("0.30"));
+        assert_eq!(d2("19.99") * d2("3"), d2("59.97"));
+        assert_eq!(d2("1.00") - d2("1.01"), d2("-0.01"));
+        assert_eq!(-d2("2.50"), d2("-2.50"));
+        assert_eq!(Money::from_int(7), Some(d2("7")));
+        assert_eq!(d2("-7.99").trunc(), -7);
+        assert_eq!(d2("10") / d2("4"), d2("2.5"));
+    }
+
+    #[test]
+    fn test_rounding_modes() {
+        let cases = [
+            // 值, 保留一位小数时 HalfEven, HalfUp, Truncate 的结果
+            ("0.25", "0.20", "0.30", "0.20"),
+            ("0.35", "0.40", "0.40", "0.30"),
+            ("0.26", "0.30", "0.30", "0.20"),
+            ("-0.25", "-0.20", "-0.30", "-0.20"),
+            ("-0.35", "-0.40", "-0.40", "-0.30"),
+            ("-0.24", "-0.20", "-0.20", "-0.20"),
+        ];
+        for &(x, even, up, trunc) in &cases {
+            let x = d2(x);
+            assert_eq!(x.round(1, Rounding::HalfEven), d2(even), "{}", x);
+            assert_eq!(x.round(1, Rounding::HalfUp), d2(up), "{}", x);
+            assert_eq!(x.round(1, Rounding::Truncate), d2(trunc), "{}", x);
+        }
+        // 2 / 3 = 0.666...
+        let (two, three) = (d2("2"), d2("3"));
+        assert_eq!(two.checked_div(three, Rounding::HalfUp), Some(d2("0.67")));
+        assert_eq!(two.checked_div(three, Rounding::Truncate), Some(d2("0.66")));
+        // 0.05 * 0.5 = 0.025
+        let (a, b) = (d2("0.05"), d2("0.5"));
+        assert_eq!(a.checked_mul(b, Rounding::HalfEven), Some(d2("0.02")));
+        assert_eq!(a.checked_mul(b, Rounding::HalfUp), Some(d2("0.03")));
+        assert_eq!((-a).checked_mul(b, Rounding::HalfUp), Some(d2("-0.03")));
+
+        let x: Decimal<4> = "1.2345".parse().unwrap();
+        assert_eq!(x.rescale::<2>(Rounding::HalfEven), Some(d2("1.23")));
+        assert_eq!(
+            x.rescale::<6>(Rounding::Truncate).unwrap().to_string(),
+            "1.234500"
+        );
+        assert_eq!(Decimal::<0>::MAX.rescale::<1>(Rounding::Truncate), None);
+    }
+
+    #[test]
+    fn test_checked_and_saturating() {
+        let big = Money::from_raw(i128::MAX - 1);
+        assert_eq!(big.checked_add(d2("0.01")), Some(Money::MAX));
+        assert_eq!(big.checked_add(d2("0.02")), None);
+        assert_eq!(big.saturating_add(d2("1")), Money::MAX);
+        assert_eq!(Money::MIN.saturating_sub(d2("1")), Money::MIN);
+        assert_eq!(big.checked_mul(d2("2"), Rounding::HalfEven), None);
+        assert_eq!(big.saturating_mul(d2("-2"), Rounding::HalfEven), Money::MIN);
+        assert_eq!(d2("1").checked_div(Money::ZERO, Rounding::HalfEven), None);
+        assert_eq!(
+            d2("1").saturating_div(d2("0.01"), Rounding::HalfEven),
+            d2("100")
+        );
+        assert_eq!(
+            big.saturating_div(d2("0.01"), Rounding::HalfEven),
+            Money::MAX
+        );
+        // 中间结果超出 i128 但最终结果在范围内
+        let huge = Money::from_raw(i128::MAX / 3);
+        assert_eq!(
+            huge.checked_mul(d2("2"), Rounding::HalfEven),
+            Some(huge + huge)
+        );
+        assert_eq!(huge.checked_div(d2("1"), Rounding::HalfEven), Some(huge));
+
+        // 边界值舍入进位后超出范围
+        assert_eq!(Money::MAX.checked_round(1, Rounding::HalfUp), None);
+        assert_eq!(Money::MIN.checked_round(1, Rounding::HalfUp), None);
+        assert_eq!(
+            Money::MAX.checked_round(1, Rounding::Truncate),
+            Some(Money::from_raw(i128::MAX / 10 * 10))
+        );
+        assert_eq!(
+            Money::MAX.checked_round(2, Rounding::HalfUp),
+            Some(Money::MAX)
+        );
+        assert_eq!(Money::MIN.checked_abs(), None);
+        assert_eq!(
+            Money::from_raw(i128::MIN + 1).checked_abs(),
+            Some(Money::MAX)
+        );
+        assert_eq!(d2("-1.25").checked_abs(), Some(d2("1.25")));
+    }
+
+    #[test]
+    #[should_panic(expected = "attempt to round with overflow")]
+    fn test_round_overflow_panics() {
+        let _ = Money::MAX.round(1, Rounding::HalfUp);
+    }
+
+    #[test]
+    #[should_panic(expected = "attempt to add with overflow")]
+    fn test_add_overflow_panics() {
+        let _ = Money::MAX + d2("0.01");
+    }
+}
@@ -0,0 +1,230 @@
+//! 可配置的 FizzBuzz 规则引擎
+//! 每条规则由匹配条件（整除或任意谓词）、输出单词和优先级组成，
+//! 规则按优先级从高到低排列，同优先级按注册顺序排列。
+//! 拼接策略决定多条规则同时命中时的输出：全部拼接，或者只取第一条。
+use std::fmt;
+
+/// 规则的匹配条件
+pub enum Matcher {
+    /// x 能被该数整除时命中，除数为 0 时永远不命中
+    Divisor(i32),
+    /// 谓词返回 true 时命中
+    Predicate(Box<dyn Fn(i32) -> bool>),
+}
+
+impl Matcher {
+    fn matches(&self, x: i32) -> bool {
+        match self {
+            // wrapping_rem 可以避免 i32::MIN % -1 溢出
+            Matcher::Divisor(d) => *d != 0 && x.wrapping_rem(*d) == 0,
+            Matcher::Predicate(f) => f(x),
+        }
+    }
+}
+
+/// 多条规则同时命中时的拼接策略
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Policy {
+    /// 按优先级顺序拼接所有命中的单词，15 => "fizzbuzz"
+    Concat,
+    /// 只输出优先级最高的命中单词
+    First,
+}
+
+struct Rule {
+    priority: i32,
+    matcher: Matcher,
+    word: String,
+}
+
+pub struct FizzBuzzRules {
+    rules: Vec<Rule>,
+    policy: Policy,
+}
+
+impl FizzBuzzRules {
+    /// 空规则集，所有输入都原样输出数字
+    pub fn new() -> Self {
+        FizzBuzzRules {
+            rules: Vec::new(),
+            policy: Policy::Concat,
+        }
+    }
+
+    /// 注册一条优先级为 0 的整除规则
+    pub fn divisor(self, divisor: i32, word: impl Into<String>) -> Self {
+        self.rule(0, Matcher::Divisor(divisor), word)
+    }
+
+    /// 注册一条优先级为 0 的谓词规则
+    pub fn predicate<F>(self, f: F, word: impl Into<String>) -> Self
+    where
+        F: Fn(i32) -> bool + 'static,
+    {
+        self.rule(0, Matcher::Predicate(Box::new(f)), word)
+    }
+
+    /// 注册一条指定优先级的规则，优先级越大越靠前
+    pub fn rule(mut self, priority: i32, matcher: Matcher, word: impl Into<String>) -> Self {
+        // 插入到所有优先级不低于它的规则之后，保证同优先级按注册顺序排列
+        let pos = self
+            .rules
+            .iter()
+            .position(|r| r.priority < priority)
+            .unwrap_or(self.rules.len());
+        self.rules.insert(
+            pos,
+            Rule {
+                priority,
+                matcher,
+                word: word.into(),
+            },
+        );
+        self
+    }
+
+    pub fn policy(mut self, policy: Policy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// 将 x 的结果写入 out，不需要分配 String
+    pub fn write_to<W: fmt::Write>(&self, x: i32, out: &mut W) -> fmt::Result {
+        let mut matched = false;
+        for rule in self.rules.iter().filter(|r| r.matcher.matches(x)) {
+            out.write_str(&rule.word)?;
+            matched = true;
+            if self.policy == Policy::First {
+                break;
+            }
+        }
+        if !matched {
+            write!(out, "{}", x)?;
+        }
+        Ok(())
+    }
+
+    pub fn apply(&self, x: i32) -> String {
+        self.line(x).to_string()
+    }
+
+    /// 惰性的单行结果，只有在格式化输出时才会计算
+    pub fn line(&self, x: i32) -> Line<'_> {
+        Line { rules: self, x }
+    }
+
+    /// 流式迭代一个范围，每一项都是借用规则集的 Line，迭代本身不会分配内存
+    pub fn iter<I>(&self, range: I) -> Lines<'_, I::IntoIter>
+    where
+        I: IntoIterator<Item = i32>,
+    {
+        Lines {
+            rules: self,
+            inner: range.into_iter(),
+        }
+    }
+}
+
+/// 默认规则集就是经典的 3 => fizz, 5 => buzz, 15 => fizzbuzz
+impl Default for FizzBuzzRules {
+    fn default() -> Self {
+        FizzBuzzRules::new().divisor(3, "fizz").divisor(5, "buzz")
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Line<'a> {
+    rules: &'a FizzBuzzRules,
+    x: i32,
+}
+
+impl fmt::Display for Line<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.rules.write_to(self.x, f)
+    }
+}
+
+pub struct Lines<'a, I> {
+    rules: &'a FizzBuzzRules,
+    inner: I,
+}
+
+impl<'a, I: Iterator<Item = i32>> Iterator for Lines<'a, I> {
+    type Item = Line<'a>;
+
+    fn next(&mut self) -> Option<Line<'a>> {
+        self.inner.next().map(|x| self.rules.line(x))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, I: DoubleEndedIterator<Item = i32>> DoubleEndedIterator for Lines<'a, I> {
+    fn next_back(&mut self) -> Option<Line<'a>> {
+        self.inner.next_back().map(|x| self.rules.line(x))
+    }
+}
+
+impl<'a, I: ExactSizeIterator<Item = i32>> ExactSizeIterator for Lines<'a, I> {}
+
+#[cfg(test)]
+mod tests {
+    use super::{FizzBuzzRules, Matcher, Policy};
+    use std::fmt::Write;
+
+    #[test]
+    fn test_default_rules() {
+        let rules = FizzBuzzRules::default();
+        assert_eq!(rules.apply(15), "fizzbuzz");
+        assert_eq!(rules.apply(9), "fizz");
+        assert_eq!(rules.apply(10), "buzz");
+        assert_eq!(rules.apply(-7), "-7");
+        assert_eq!(rules.apply(0), "fizzbuzz");
+    }
+
+    #[test]
+    fn test_priority_and_policy() {
+        let rules = FizzBuzzRules::new()
+            .divisor(3, "fizz")
+            .divisor(5, "buzz")
+            .rule(10, Matcher::Divisor(7), "bazz")
+            .predicate(|x| x < 0, "neg");
+        assert_eq!(rules.apply(105), "bazzfizzbuzz");
+        assert_eq!(rules.apply(-3), "fizzneg");
+        assert_eq!(rules.apply(0), "bazzfizzbuzz");
+
+        let rules = rules.policy(Policy::First);
+        assert_eq!(rules.apply(105), "bazz");
+        assert_eq!(rules.apply(-3), "fizz");
+        assert_eq!(rules.apply(-1), "neg");
+        assert_eq!(rules.apply(1), "1");
+    }
+
+    #[test]
+    fn test_degenerate_divisors() {
+        let rules = FizzBuzzRules::new().divisor(0, "zero").divisor(-1, "all");
+        assert_eq!(rules.apply(i32::MIN), "all");
+        assert_eq!(rules.apply(0), "all");
+        assert_eq!(FizzBuzzRules::new().apply(42), "42");
+    }
+
+    #[test]
+    fn test_streaming_iter() {
+        let rules = FizzBuzzRules::default();
+        let mut out = String::new();
+        for line in rules.iter(1..=15) {
+            write!(out, "{} ", line).unwrap();
+        }
+        assert_eq!(
+            out,
+            "1 2 fizz 4 buzz fizz 7 8 fizz buzz 11 fizz 13 14 fizzbuzz "
+        );
+        assert_eq!(rules.iter(0..1_000_000).len(), 1_000_000);
+        assert_eq!(
+            rules.iter(1..=15).next_back().unwrap().to_string(),
+            "fizzbuzz"
+        );
+    }
+}
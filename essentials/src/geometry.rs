@@ -0,0 +1,498 @@
+//! 围绕 smart_pointer::Point 的二维几何
+//! Point 同时被当作点和向量使用，通过 std::ops 实现向量运算。
+//! 所有"点在哪一侧"的判断都基于 orient2d，它先用浮点误差界快速判断，
+//! 无法确定时退回到精确的浮点扩展（expansion）运算，因此共线等退化输入也不会误判。
+use crate::smart_pointer::Point;
+use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign};
+
+impl Point {
+    pub fn new(x: f64, y: f64) -> Self {
+        Point { x, y }
+    }
+
+    pub fn dot(self, rhs: Point) -> f64 {
+        self.x * rhs.x + self.y * rhs.y
+    }
+
+    /// 二维叉积（z 分量），正数表示 rhs 在 self 的逆时针方向
+    pub fn cross(self, rhs: Point) -> f64 {
+        self.x * rhs.y - self.y * rhs.x
+    }
+
+    pub fn norm(self) -> f64 {
+        self.x.hypot(self.y)
+    }
+
+    pub fn distance(self, other: Point) -> f64 {
+        (self - other).norm()
+    }
+
+    /// 数值上的重合，-0.0 和 0.0 视为同一位置；Point 的 == 是全序意义上的相等
+    pub fn coincides(self, other: Point) -> bool {
+        self.x == other.x && self.y == other.y
+    }
+}
+
+impl Add for Point {
+    type Output = Point;
+
+    fn add(self, rhs: Point) -> Point {
+        Point::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl Sub for Point {
+    type Output = Point;
+
+    fn sub(self, rhs: Point) -> Point {
+        Point::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+impl Neg for Point {
+    type Output = Point;
+
+    fn neg(self) -> Point {
+        Point::new(-self.x, -self.y)
+    }
+}
+
+impl Mul<f64> for Point {
+    type Output = Point;
+
+    fn mul(self, k: f64) -> Point {
+        Point::new(self.x * k, self.y * k)
+    }
+}
+
+impl Div<f64> for Point {
+    type Output = Point;
+
+    fn div(self, k: f64) -> Point {
+        Point::new(self.x / k, self.y / k)
+    }
+}
+
+impl AddAssign for Point {
+    fn add_assign(&mut self, rhs: Point) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for Point {
+    fn sub_assign(&mut self, rhs: Point) {
+        *self = *self - rhs;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Orientation {
+    CounterClockwise,
+    Clockwise,
+    Collinear,
+}
+
+// 精确求和与精确乘积：返回 (近似值, 误差)，两者之和严格等于精确结果
+fn two_sum(a: f64, b: f64) -> (f64, f64) {
+    let x = a + b;
+    let bv = x - a;
+    let av = x - bv;
+    (x, (a - av) + (b - bv))
+}
+
+fn two_product(a: f64, b: f64) -> (f64, f64) {
+    let x = a * b;
+    (x, a.mul_add(b, -x))
+}
+
+/// 向按绝对值递增、互不重叠的浮点扩展中加入一个数，同时去掉零分量（Shewchuk 的 GROW-EXPANSION）
+fn grow_expansion(e: &mut Vec<f64>, b: f64) {
+    let mut q = b;
+    let mut out = Vec::with_capacity(e.len() + 1);
+    for &ei in e.iter() {
+        let (sum, err) = two_sum(q, ei);
+        if err != 0.0 {
+            out.push(err);
+        }
+        q = sum;
+    }
+    if q != 0.0 {
+        out.push(q);
+    }
+    *e = out;
+}
+
+/// (a - c) x (b - c) 的精确符号
+fn orient2d_exact(a: Point, b: Point, c: Point) -> f64 {
+    let two_diff = |x: f64, y: f64| {
+        let (hi, lo) = two_sum(x, -y);
+        [lo, hi]
+    };
+    let acx = two_diff(a.x, c.x);
+    let acy = two_diff(a.y, c.y);
+    let bcx = two_diff(b.x, c.x);
+    let bcy = two_diff(b.y, c.y);
+
+    let mut det = Vec::new();
+    for (&l, &r, sign) in [(&acx, &bcy, 1.0), (&acy, &bcx, -1.0)].iter() {
+        for &p in l.iter() {
+            for &q in r.iter() {
+                let (hi, lo) = two_product(p, q);
+                grow_expansion(&mut det, sign * lo);
+                grow_expansion(&mut det, sign * hi);
+            }
+        }
+    }
+    // 互不重叠的扩展，其符号就是绝对值最大（最后一个）分量的符号
+    det.last().copied().unwrap_or(0.0)
+}
+
+/// c 相对于有向直线 a -> b 的位置，在浮点输入上是精确的
+pub fn orient2d(a: Point, b: Point, c: Point) -> Orientation {
+    let left = (a.x - c.x) * (b.y - c.y);
+    let right = (a.y - c.y) * (b.x - c.x);
+    let det = left - right;
+    // Shewchuk 给出的误差界，超出误差界时浮点结果的符号一定正确
+    let err_bound = (3.0 + 16.0 * f64::EPSILON) * f64::EPSILON * (left.abs() + right.abs());
+    let det = if det.abs() > err_bound {
+        det
+    } else {
+        orient2d_exact(a, b, c)
+    };
+    if det > 0.0 {
+        Orientation::CounterClockwise
+    } else if det < 0.0 {
+        Orientation::Clockwise
+    } else {
+        Orientation::Collinear
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Segment {
+    pub a: Point,
+    pub b: Point,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Intersection {
+    Point(Point),
+    /// 共线且重叠的部分
+    Segment(Segment),
+}
+
+impl Segment {
+    pub fn new(a: Point, b: Point) -> Self {
+        Segment { a, b }
+    }
+
+    pub fn length(&self) -> f64 {
+        self.a.distance(self.b)
+    }
+
+    /// 已知 p 与线段共线时，p 是否落在线段的包围盒内
+    fn in_bounding_box(&self, p: Point) -> bool {
+        p.x >= self.a.x.min(self.b.x)
+            && p.x <= self.a.x.max(self.b.x)
+            && p.y >= self.a.y.min(self.b.y)
+            && p.y <= self.a.y.max(self.b.y)
+    }
+
+    pub fn contains(&self, p: Point) -> bool {
+        orient2d(self.a, self.b, p) == Orientation::Collinear && self.in_bounding_box(p)
+    }
+
+    /// 判断两条线段是否相交（包括端点接触和共线重叠），结果是精确的
+    pub fn intersects(&self, other: &Segment) -> bool {
+        let o1 = orient2d(self.a, self.b, other.a);
+        let o2 = orient2d(self.a, self.b, other.b);
+        let o3 = orient2d(other.a, other.b, self.a);
+        let o4 = orient2d(other.a, other.b, self.b);
+        if o1 != o2
+            && o3 != o4
+            && [o1, o2, o3, o4]
+                .iter()
+                .all(|&o| o != Orientation::Collinear)
+        {
+            return true;
+        }
+        self.contains(other.a)
+            || self.contains(other.b)
+            || other.contains(self.a)
+            || other.contains(self.b)
+    }
+
+    /// 计算交点或重叠部分，交点坐标本身是浮点近似值
+    pub fn intersection(&self, other: &Segment) -> Option<Intersection> {
+        if !self.intersects(other) {
+            return None;
+        }
+        let collinear = orient2d(self.a, self.b, other.a) == Orientation::Collinear
+            && orient2d(self.a, self.b, other.b) == Orientation::Collinear;
+        if !collinear {
+            // 只有一个交点：先处理端点接触，保证返回的端点精确
+            for &p in [other.a, other.b].iter() {
+                if self.contains(p) {
+                    return Some(Intersection::Point(p));
+                }
+            }
+            for &p in [self.a, self.b].iter() {
+                if other.contains(p) {
+                    return Some(Intersection::Point(p));
+                }
+            }
+            let d1 = self.b - self.a;
+            let d2 = other.b - other.a;
+            let t = (other.a - self.a).cross(d2) / d1.cross(d2);
+            return Some(Intersection::Point(self.a + d1 * t));
+        }
+
+        // 共线：把四个端点投影到方向向量上，取中间的两个
+        let dir = if self.a.coincides(self.b) {
+            other.b - other.a
+        } else {
+            self.b - self.a
+        };
+        let key = |p: Point| (p - self.a).dot(dir);
+        let ordered = |s: &Segment| {
+            if key(s.a) <= key(s.b) {
+                (s.a, s.b)
+            } else {
+                (s.b, s.a)
+            }
+        };
+        let (s_min, s_max) = ordered(self);
+        let (o_min, o_max) = ordered(other);
+        let lo = if key(s_min) >= key(o_min) {
+            s_min
+        } else {
+            o_min
+        };
+        let hi = if key(s_max) <= key(o_max) {
+            s_max
+        } else {
+            o_max
+        };
+        if lo.coincides(hi) {
+            Some(Intersection::Point(lo))
+        } else {
+            Some(Intersection::Segment(Segment::new(lo, hi)))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Location {
+    Inside,
+    Outside,
+    Boundary,
+}
+
+/// 简单多边形，顶点按顺序排列，首尾自动相连
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polygon {
+    pub vertices: Vec<Point>,
+}
+
+impl Polygon {
+    pub fn new(vertices: Vec<Point>) -> Self {
+        Polygon { vertices }
+    }
+
+    pub fn edges(&self) -> impl Iterator<Item = Segment> + '_ {
+        let n = self.vertices.len();
+        (0..n).map(move |i| Segment::new(self.vertices[i], self.vertices[(i + 1) % n]))
+    }
+
+    /// 鞋带公式，逆时针为正
+    pub fn signed_area(&self) -> f64 {
+        self.edges().map(|e| e.a.cross(e.b)).sum::<f64>() / 2.0
+    }
+
+    pub fn area(&self) -> f64 {
+        self.signed_area().abs()
+    }
+
+    /// 面积为零的退化多边形没有质心
+    pub fn centroid(&self) -> Option<Point> {
+        let area = self.signed_area();
+        if area == 0.0 {
+            return None;
+        }
+        // 以第一个顶点为原点计算，减小大坐标带来的舍入误差
+        let origin = *self.vertices.first()?;
+        let mut acc = Point::new(0.0, 0.0);
+        for e in self.edges() {
+            let (a, b) = (e.a - origin, e.b - origin);
+            acc += (a + b) * a.cross(b);
+        }
+        Some(origin + acc / (6.0 * area))
+    }
+
+    /// 非零环绕数规则，边界上的点单独返回 Boundary
+    pub fn locate(&self, p: Point) -> Location {
+        let mut winding = 0;
+        for e in self.edges() {
+            if e.contains(p) {
+                return Location::Boundary;
+            }
+            if e.a.y <= p.y {
+                if e.b.y > p.y && orient2d(e.a, e.b, p) == Orientation::CounterClockwise {
+                    winding += 1;
+                }
+            } else if e.b.y <= p.y && orient2d(e.a, e.b, p) == Orientation::Clockwise {
+                winding -= 1;
+            }
+        }
+        if winding == 0 {
+            Location::Outside
+        } else {
+            Location::Inside
+        }
+    }
+
+    pub fn contains(&self, p: Point) -> bool {
+        self.locate(p) != Location::Outside
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{orient2d, Intersection, Location, Orientation, Point, Polygon, Segment};
+
+    fn p(x: f64, y: f64) -> Point {
+        Point::new(x, y)
+    }
+
+    #[test]
+    fn test_vector_ops() {
+        let (a, b) = (p(1.0, 2.0), p(3.0, -1.0));
+        assert_eq!(a + b, p(4.0, 1.0));
+        assert_eq!(a - b, p(-2.0, 3.0));
+        assert_eq!(-a, p(-1.0, -2.0));
+        assert_eq!(a * 2.0, p(2.0, 4.0));
+        assert_eq!(b / 2.0, p(1.5, -0.5));
+        assert_eq!(a.dot(b), 1.0);
+        assert_eq!(a.cross(b), -7.0);
+        assert_eq!(p(0.0, 0.0).distance(p(3.0, 4.0)), 5.0);
+        let mut c = a;
+        c += b;
+        c -= a;
+        assert_eq!(c, b);
+    }
+
+    #[test]
+    fn test_orient2d_robust() {
+        assert_eq!(
+            orient2d(p(0.0, 0.0), p(1.0, 0.0), p(0.0, 1.0)),
+            Orientation::CounterClockwise
+        );
+        assert_eq!(
+            orient2d(p(0.0, 0.0), p(1.0, 0.0), p(0.0, -1.0)),
+            Orientation::Clockwise
+        );
+        assert_eq!(
+            orient2d(p(0.0, 0.0), p(1.0, 1.0), p(3.0, 3.0)),
+            Orientation::Collinear
+        );
+
+        // 经典的近似共线反例：朴素的浮点计算在这些点上会得出互相矛盾的结果
+        let a = p(12.0, 12.0);
+        let b = p(24.0, 24.0);
+        let step = f64::EPSILON;
+        for i in 0..64 {
+            for j in 0..64 {
+                let c = p(0.5 + i as f64 * step, 0.5 + j as f64 * step);
+                let expected = match (c.y - c.x).partial_cmp(&0.0).unwrap() {
+                    std::cmp::Ordering::Greater => Orientation::CounterClockwise,
+                    std::cmp::Ordering::Less => Orientation::Clockwise,
+                    std::cmp::Ordering::Equal => Orientation::Collinear,
+                };
+                assert_eq!(orient2d(a, b, c), expected, "{:?}", c);
+                assert_eq!(orient2d(c, a, b), expected, "{:?}", c);
+            }
+        }
+        // 0.1 不能精确表示，但 y = x 上的点依然严格共线
+        assert_eq!(
+            orient2d(p(0.1, 0.1), p(0.2, 0.2), p(0.3, 0.3)),
+            Orientation::Collinear
+        );
+    }
+
+    #[test]
+    fn test_segment_intersection() {
+        let s1 = Segment::new(p(0.0, 0.0), p(4.0, 4.0));
+        let s2 = Segment::new(p(0.0, 4.0), p(4.0, 0.0));
+        assert_eq!(s1.intersection(&s2), Some(Intersection::Point(p(2.0, 2.0))));
+
+        let touching = Segment::new(p(4.0, 4.0), p(5.0, 0.0));
+        assert_eq!(
+            s1.intersection(&touching),
+            Some(Intersection::Point(p(4.0, 4.0)))
+        );
+
+        let parallel = Segment::new(p(0.0, 1.0), p(4.0, 5.0));
+        assert!(!s1.intersects(&parallel));
+        assert_eq!(s1.intersection(&parallel), None);
+
+        let collinear_disjoint = Segment::new(p(5.0, 5.0), p(6.0, 6.0));
+        assert!(!s1.intersects(&collinear_disjoint));
+
+        let overlap = Segment::new(p(6.0, 6.0), p(2.0, 2.0));
+        assert_eq!(
+            s1.intersection(&overlap),
+            Some(Intersection::Segment(Segment::new(
+                p(2.0, 2.0),
+                p(4.0, 4.0)
+            )))
+        );
+        let end_to_end = Segment::new(p(4.0, 4.0), p(8.0, 8.0));
+        assert_eq!(
+            s1.intersection(&end_to_end),
+            Some(Intersection::Point(p(4.0, 4.0)))
+        );
+
+        let degenerate = Segment::new(p(1.0, 1.0), p(1.0, 1.0));
+        assert_eq!(
+            s1.intersection(&degenerate),
+            Some(Intersection::Point(p(1.0, 1.0)))
+        );
+        assert_eq!(s1.length(), 32f64.sqrt());
+    }
+
+    #[test]
+    fn test_polygon() {
+        // 逆时针的 L 形
+        let poly = Polygon::new(vec![
+            p(0.0, 0.0),
+            p(2.0, 0.0),
+            p(2.0, 1.0),
+            p(1.0, 1.0),
+            p(1.0, 2.0),
+            p(0.0, 2.0),
+        ]);
+        assert_eq!(poly.signed_area(), 3.0);
+        let cw = Polygon::new(poly.vertices.iter().rev().copied().collect());
+        assert_eq!(cw.signed_area(), -3.0);
+        assert_eq!(cw.area(), 3.0);
+
+        for c in [poly.centroid().unwrap(), cw.centroid().unwrap()].iter() {
+            assert!(c.distance(p(5.0 / 6.0, 5.0 / 6.0)) < 1e-12);
+        }
+        let line = Polygon::new(vec![p(0.0, 0.0), p(1.0, 1.0), p(2.0, 2.0)]);
+        assert_eq!(line.centroid(), None);
+
+        for poly in [&poly, &cw].iter() {
+            assert_eq!(poly.locate(p(0.5, 0.5)), Location::Inside);
+            assert_eq!(poly.locate(p(1.5, 1.5)), Location::Outside);
+            assert_eq!(poly.locate(p(1.0, 1.5)), Location::Boundary);
+            assert_eq!(poly.locate(p(2.0, 0.0)), Location::Boundary);
+            // 射线穿过顶点的情况
+            assert_eq!(poly.locate(p(-1.0, 1.0)), Location::Outside);
+            assert_eq!(poly.locate(p(0.5, 1.0)), Location::Inside);
+            assert_eq!(poly.locate(p(3.0, 0.0)), Location::Outside);
+        }
+        assert!(poly.contains(p(1.0, 1.0)));
+        assert!(!poly.contains(p(1.1, 1.1)));
+    }
+}
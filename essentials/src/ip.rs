@@ -0,0 +1,403 @@
+//! IPv4/IPv6 地址
+//! V4 仍然是携带 4 个 u8 参数的枚举值（本质上是函数指针 fn(u8, u8, u8, u8) -> IpAddr），
+//! V6 使用一个 u128 保存 128 位地址，而不是未经解析的字符串。
+//! 派生的 Ord 先按变体排序（所有 V4 都小于 V6），同变体内按数值大小排序。
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum IpAddr {
+    // 携带了参数的枚举值，本质上属于函数指针类型
+    V4(u8, u8, u8, u8),
+    V6(u128),
+}
+
+impl IpAddr {
+    pub fn octets(&self) -> Option<[u8; 4]> {
+        match *self {
+            IpAddr::V4(a, b, c, d) => Some([a, b, c, d]),
+            IpAddr::V6(_) => None,
+        }
+    }
+
+    /// IPv6 的 8 个 16 位分段，V4 返回 None
+    pub fn segments(&self) -> Option<[u16; 8]> {
+        match *self {
+            IpAddr::V4(..) => None,
+            IpAddr::V6(bits) => {
+                let mut segs = [0u16; 8];
+                for (i, seg) in segs.iter_mut().enumerate() {
+                    *seg = (bits >> (112 - 16 * i)) as u16;
+                }
+                Some(segs)
+            }
+        }
+    }
+
+    pub fn from_segments(segs: [u16; 8]) -> Self {
+        IpAddr::V6(bits_from_segments(segs))
+    }
+
+    /// 地址位宽，V4 为 32，V6 为 128
+    pub fn bit_len(&self) -> u8 {
+        match self {
+            IpAddr::V4(..) => 32,
+            IpAddr::V6(_) => 128,
+        }
+    }
+
+    /// 地址的数值，V4 占用低 32 位
+    pub fn to_bits(self) -> u128 {
+        match self {
+            IpAddr::V4(a, b, c, d) => u32::from_be_bytes([a, b, c, d]) as u128,
+            IpAddr::V6(bits) => bits,
+        }
+    }
+
+    pub fn is_ipv4(&self) -> bool {
+        matches!(self, IpAddr::V4(..))
+    }
+
+    pub fn is_ipv6(&self) -> bool {
+        matches!(self, IpAddr::V6(_))
+    }
+
+    /// 0.0.0.0 和 ::
+    pub fn is_unspecified(&self) -> bool {
+        match *self {
+            IpAddr::V4(a, b, c, d) => [a, b, c, d] == [0; 4],
+            IpAddr::V6(bits) => bits == 0,
+        }
+    }
+
+    /// 127.0.0.0/8 和 ::1
+    pub fn is_loopback(&self) -> bool {
+        match *self {
+            IpAddr::V4(a, ..) => a == 127,
+            IpAddr::V6(bits) => bits == 1,
+        }
+    }
+
+    /// RFC 1918 私有地址，以及 IPv6 的唯一本地地址 fc00::/7
+    pub fn is_private(&self) -> bool {
+        match *self {
+            IpAddr::V4(10, ..) | IpAddr::V4(192, 168, ..) => true,
+            IpAddr::V4(172, b, ..) => (16..=31).contains(&b),
+            IpAddr::V4(..) => false,
+            IpAddr::V6(bits) => bits >> 121 == 0xfc >> 1,
+        }
+    }
+
+    /// 224.0.0.0/4 和 ff00::/8
+    pub fn is_multicast(&self) -> bool {
+        match *self {
+            IpAddr::V4(a, ..) => a >> 4 == 0xe,
+            IpAddr::V6(bits) => bits >> 120 == 0xff,
+        }
+    }
+
+    /// 169.254.0.0/16 和 fe80::/10
+    pub fn is_link_local(&self) -> bool {
+        match *self {
+            IpAddr::V4(a, b, ..) => a == 169 && b == 254,
+            IpAddr::V6(bits) => bits >> 118 == 0xfe80 >> 6,
+        }
+    }
+
+    /// ::ffff:a.b.c.d 形式的 IPv4 映射地址
+    pub fn to_ipv4_mapped(self) -> Option<IpAddr> {
+        match self {
+            IpAddr::V6(bits) if bits >> 32 == 0xffff => {
+                let [a, b, c, d] = (bits as u32).to_be_bytes();
+                Some(IpAddr::V4(a, b, c, d))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl From<[u8; 4]> for IpAddr {
+    fn from(o: [u8; 4]) -> Self {
+        IpAddr::V4(o[0], o[1], o[2], o[3])
+    }
+}
+
+impl From<u32> for IpAddr {
+    fn from(bits: u32) -> Self {
+        IpAddr::from(bits.to_be_bytes())
+    }
+}
+
+impl From<u128> for IpAddr {
+    fn from(bits: u128) -> Self {
+        IpAddr::V6(bits)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AddrParseError(&'static str);
+
+impl fmt::Display for AddrParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid IP address: {}", self.0)
+    }
+}
+
+impl Error for AddrParseError {}
+
+fn bits_from_segments(segs: [u16; 8]) -> u128 {
+    segs.iter().fold(0u128, |acc, &s| (acc << 16) | s as u128)
+}
+
+fn parse_v4(s: &str) -> Result<[u8; 4], AddrParseError> {
+    let mut octets = [0u8; 4];
+    let mut parts = s.split('.');
+    for octet in octets.iter_mut() {
+        let part = parts.next().ok_or(AddrParseError("too few octets"))?;
+        if part.is_empty() || part.len() > 3 || !part.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(AddrParseError("bad octet"));
+        }
+        // 前导零在一些实现中会被当成八进制，这里直接拒绝
+        if part.len() > 1 && part.starts_with('0') {
+            return Err(AddrParseError("leading zero in octet"));
+        }
+        *octet = part
+            .parse()
+            .map_err(|_| AddrParseError("octet out of range"))?;
+    }
+    if parts.next().is_some() {
+        return Err(AddrParseError("too many octets"));
+    }
+    Ok(octets)
+}
+
+/// 解析以冒号分隔的分段，最后一段允许是内嵌的 IPv4 地址（占两个分段）
+fn parse_groups(s: &str, allow_v4: bool, out: &mut Vec<u16>) -> Result<(), AddrParseError> {
+    if s.is_empty() {
+        return Ok(());
+    }
+    let mut groups = s.split(':').peekable();
+    while let Some(group) = groups.next() {
+        if groups.peek().is_none() && allow_v4 && group.contains('.') {
+            let [a, b, c, d] = parse_v4(group)?;
+            out.push(u16::from_be_bytes([a, b]));
+            out.push(u16::from_be_bytes([c, d]));
+        } else {
+            // from_str_radix 允许前导 +，这里只接受十六进制数字
+            if group.is_empty() || group.len() > 4 || !group.bytes().all(|b| b.is_ascii_hexdigit())
+            {
+                return Err(AddrParseError("bad group"));
+            }
+            let seg = u16::from_str_radix(group, 16).map_err(|_| AddrParseError("bad group"))?;
+            out.push(seg);
+        }
+        if out.len() > 8 {
+            return Err(AddrParseError("too many groups"));
+        }
+    }
+    Ok(())
+}
+
+fn parse_v6(s: &str) -> Result<u128, AddrParseError> {
+    let mut head = Vec::with_capacity(8);
+    let mut tail = Vec::with_capacity(8);
+    let mut segs = [0u16; 8];
+    match s.find("::") {
+        Some(i) => {
+            let (h, t) = (&s[..i], &s[i + 2..]);
+            if t.contains("::") {
+                return Err(AddrParseError("multiple '::'"));
+            }
+            parse_groups(h, false, &mut head)?;
+            parse_groups(t, true, &mut tail)?;
+            // :: 至少要代表一个全零分段
+            if head.len() + tail.len() > 7 {
+                return Err(AddrParseError("too many groups"));
+            }
+            segs[..head.len()].copy_from_slice(&head);
+            segs[8 - tail.len()..].copy_from_slice(&tail);
+        }
+        None => {
+            parse_groups(s, true, &mut head)?;
+            if head.len() != 8 {
+                return Err(AddrParseError("too few groups"));
+            }
+            segs.copy_from_slice(&head);
+        }
+    }
+    Ok(bits_from_segments(segs))
+}
+
+impl FromStr for IpAddr {
+    type Err = AddrParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.contains(':') {
+            parse_v6(s).map(IpAddr::V6)
+        } else {
+            parse_v4(s).map(IpAddr::from)
+        }
+    }
+}
+
+/// IPv6 按 RFC 5952 输出：小写十六进制、去掉前导零，
+/// 最长的（长度至少为 2 的）连续全零分段压缩为 ::，长度相同时压缩第一段，
+/// IPv4 映射地址输出为 ::ffff:a.b.c.d
+impl fmt::Display for IpAddr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let IpAddr::V4(a, b, c, d) = *self {
+            return write!(f, "{}.{}.{}.{}", a, b, c, d);
+        }
+        if let Some(IpAddr::V4(a, b, c, d)) = self.to_ipv4_mapped() {
+            return write!(f, "::ffff:{}.{}.{}.{}", a, b, c, d);
+        }
+        let segs = self.segments().unwrap();
+
+        let (mut best_start, mut best_len) = (0, 0);
+        let mut i = 0;
+        while i < 8 {
+            if segs[i] == 0 {
+                let start = i;
+                while i < 8 && segs[i] == 0 {
+                    i += 1;
+                }
+                if i - start > best_len {
+                    best_start = start;
+                    best_len = i - start;
+                }
+            } else {
+                i += 1;
+            }
+        }
+
+        let write_run = |f: &mut fmt::Formatter, run: &[u16]| -> fmt::Result {
+            for (i, seg) in run.iter().enumerate() {
+                if i > 0 {
+                    f.write_str(":")?;
+                }
+                write!(f, "{:x}", seg)?;
+            }
+            Ok(())
+        };
+        if best_len < 2 {
+            return write_run(f, &segs);
+        }
+        write_run(f, &segs[..best_start])?;
+        f.write_str("::")?;
+        write_run(f, &segs[best_start + best_len..])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IpAddr;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_display_v4() {
+        assert_eq!(ip("127.0.0.1"), IpAddr::V4(127, 0, 0, 1));
+        assert_eq!(IpAddr::V4(192, 168, 1, 255).to_string(), "192.168.1.255");
+        for bad in &[
+            "1.2.3",
+            "1.2.3.4.5",
+            "256.0.0.1",
+            "01.2.3.4",
+            "1..2.3",
+            "a.b.c.d",
+            "",
+        ] {
+            assert!(bad.parse::<IpAddr>().is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_parse_and_display_v6() {
+        let cases = [
+            ("::", "::"),
+            ("::1", "::1"),
+            ("2001:0db8:0000:0000:0000:0000:0002:0001", "2001:db8::2:1"),
+            ("2001:db8:0:0:1:0:0:1", "2001:db8::1:0:0:1"),
+            ("2001:db8:0:1:1:1:1:1", "2001:db8:0:1:1:1:1:1"),
+            ("2001:DB8::aBcD", "2001:db8::abcd"),
+            ("fe80::", "fe80::"),
+            ("1:2:3:4:5:6:7::", "1:2:3:4:5:6:7:0"),
+            ("::ffff:192.0.2.1", "::ffff:192.0.2.1"),
+            ("64:ff9b::192.0.2.33", "64:ff9b::c000:221"),
+        ];
+        for &(input, expected) in cases.iter() {
+            let addr = ip(input);
+            assert_eq!(addr.to_string(), expected, "{}", input);
+            assert_eq!(ip(expected), addr);
+        }
+        assert_eq!(ip("::1"), IpAddr::V6(1));
+        for bad in &[
+            ":",
+            ":::",
+            "1::2::3",
+            "1:2:3:4:5:6:7:8:9",
+            "1:2:3:4:5:6:7",
+            "12345::",
+            "::g",
+            "1:2:3:4:5:6:7:8::",
+            "+1::",
+            "1:+2::",
+            "::-1",
+        ] {
+            assert!(bad.parse::<IpAddr>().is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_classification() {
+        assert!(ip("127.1.2.3").is_loopback());
+        assert!(ip("::1").is_loopback());
+        assert!(!ip("::2").is_loopback());
+
+        assert!(ip("10.1.1.1").is_private());
+        assert!(ip("172.16.0.1").is_private());
+        assert!(ip("172.31.255.255").is_private());
+        assert!(!ip("172.32.0.1").is_private());
+        assert!(ip("192.168.0.1").is_private());
+        assert!(ip("fd12:3456::1").is_private());
+        assert!(!ip("2001:db8::1").is_private());
+
+        assert!(ip("224.0.0.1").is_multicast());
+        assert!(ip("239.255.255.255").is_multicast());
+        assert!(!ip("240.0.0.1").is_multicast());
+        assert!(ip("ff02::1").is_multicast());
+
+        assert!(ip("169.254.10.1").is_link_local());
+        assert!(ip("fe80::1").is_link_local());
+        assert!(ip("febf::1").is_link_local());
+        assert!(!ip("fec0::1").is_link_local());
+
+        assert!(ip("0.0.0.0").is_unspecified());
+        assert!(ip("::").is_unspecified());
+    }
+
+    #[test]
+    fn test_ordering() {
+        let mut addrs = vec![
+            ip("::1"),
+            ip("10.0.0.2"),
+            ip("9.255.255.255"),
+            ip("::"),
+            ip("10.0.0.1"),
+        ];
+        addrs.sort();
+        assert_eq!(
+            addrs,
+            vec![
+                ip("9.255.255.255"),
+                ip("10.0.0.1"),
+                ip("10.0.0.2"),
+                ip("::"),
+                ip("::1")
+            ]
+        );
+    }
+}
@@ -0,0 +1,295 @@
+//! k-d 树空间索引
+//! 每个节点通过 Box 独占自己的左右子树，Option<Box<Node>> 既表示"可能为空"又保证了节点大小固定，
+//! 这正是 Box<T> 在递归类型中的典型用法：编译器无法确定无限递归类型的大小，而指针的大小是确定的。
+use crate::smart_pointer::Point;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// 可以放进 k-d 树的点
+pub trait KdPoint: Copy {
+    const DIM: usize;
+
+    fn coord(&self, axis: usize) -> f64;
+
+    fn dist2(&self, other: &Self) -> f64 {
+        (0..Self::DIM)
+            .map(|axis| (self.coord(axis) - other.coord(axis)).powi(2))
+            .sum()
+    }
+}
+
+impl KdPoint for Point {
+    const DIM: usize = 2;
+
+    fn coord(&self, axis: usize) -> f64 {
+        if axis == 0 {
+            self.x
+        } else {
+            self.y
+        }
+    }
+}
+
+type Link<P> = Option<Box<Node<P>>>;
+
+/// 左子树在 axis 上的坐标都不大于当前节点，右子树都不小于当前节点
+struct Node<P> {
+    point: P,
+    axis: usize,
+    left: Link<P>,
+    right: Link<P>,
+}
+
+pub struct KdTree<P> {
+    root: Link<P>,
+    len: usize,
+}
+
+/// k 近邻搜索时放进最大堆的候选点，堆顶是当前第 k 近的点
+struct Candidate<P> {
+    dist2: f64,
+    point: P,
+}
+
+impl<P> PartialEq for Candidate<P> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<P> Eq for Candidate<P> {}
+
+impl<P> PartialOrd for Candidate<P> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<P> Ord for Candidate<P> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.dist2.total_cmp(&other.dist2)
+    }
+}
+
+impl<P: KdPoint> KdTree<P> {
+    pub fn new() -> Self {
+        KdTree { root: None, len: 0 }
+    }
+
+    /// 每层按当前维度取中位数，得到平衡的树，O(n log n)
+    pub fn build(mut points: Vec<P>) -> Self {
+        let len = points.len();
+        KdTree {
+            root: Self::build_rec(&mut points, 0),
+            len,
+        }
+    }
+
+    fn build_rec(points: &mut [P], depth: usize) -> Link<P> {
+        if points.is_empty() {
+            return None;
+        }
+        let axis = depth % P::DIM;
+        let mid = points.len() / 2;
+        points.select_nth_unstable_by(mid, |a, b| a.coord(axis).total_cmp(&b.coord(axis)));
+        let (left, rest) = points.split_at_mut(mid);
+        let (point, right) = rest.split_first_mut().unwrap();
+        Some(Box::new(Node {
+            point: *point,
+            axis,
+            left: Self::build_rec(left, depth + 1),
+            right: Self::build_rec(right, depth + 1),
+        }))
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// 逐个插入不会重新平衡，大量数据建议使用 build
+    pub fn insert(&mut self, point: P) {
+        let mut link = &mut self.root;
+        let mut depth = 0;
+        while let Some(node) = link {
+            link = if point.coord(node.axis) < node.point.coord(node.axis) {
+                &mut node.left
+            } else {
+                &mut node.right
+            };
+            depth += 1;
+        }
+        *link = Some(Box::new(Node {
+            point,
+            axis: depth % P::DIM,
+            left: None,
+            right: None,
+        }));
+        self.len += 1;
+    }
+
+    pub fn nearest(&self, target: &P) -> Option<P> {
+        self.k_nearest(target, 1).pop()
+    }
+
+    /// 距离 target 最近的 k 个点，按距离从近到远排列
+    pub fn k_nearest(&self, target: &P, k: usize) -> Vec<P> {
+        let mut heap = BinaryHeap::with_capacity(k + 1);
+        if k > 0 {
+            Self::k_nearest_rec(&self.root, target, k, &mut heap);
+        }
+        heap.into_sorted_vec()
+            .into_iter()
+            .map(|c| c.point)
+            .collect()
+    }
+
+    fn k_nearest_rec(link: &Link<P>, target: &P, k: usize, heap: &mut BinaryHeap<Candidate<P>>) {
+        let node = match link {
+            Some(node) => node,
+            None => return,
+        };
+        let dist2 = node.point.dist2(target);
+        if heap.len() < k {
+            heap.push(Candidate {
+                dist2,
+                point: node.point,
+            });
+        } else if dist2 < heap.peek().unwrap().dist2 {
+            heap.pop();
+            heap.push(Candidate {
+                dist2,
+                point: node.point,
+            });
+        }
+
+        // 先搜索 target 所在的一侧，另一侧只有在分割面比当前第 k 近的点更近时才需要搜索
+        let diff = target.coord(node.axis) - node.point.coord(node.axis);
+        let (near, far) = if diff < 0.0 {
+            (&node.left, &node.right)
+        } else {
+            (&node.right, &node.left)
+        };
+        Self::k_nearest_rec(near, target, k, heap);
+        if heap.len() < k || diff * diff < heap.peek().unwrap().dist2 {
+            Self::k_nearest_rec(far, target, k, heap);
+        }
+    }
+
+    /// min 和 max 围成的轴对齐矩形（包含边界）内的所有点
+    pub fn range(&self, min: &P, max: &P) -> Vec<P> {
+        let mut out = Vec::new();
+        Self::range_rec(&self.root, min, max, &mut out);
+        out
+    }
+
+    fn range_rec(link: &Link<P>, min: &P, max: &P, out: &mut Vec<P>) {
+        let node = match link {
+            Some(node) => node,
+            None => return,
+        };
+        let inside = (0..P::DIM).all(|axis| {
+            let c = node.point.coord(axis);
+            c >= min.coord(axis) && c <= max.coord(axis)
+        });
+        if inside {
+            out.push(node.point);
+        }
+        let c = node.point.coord(node.axis);
+        if min.coord(node.axis) <= c {
+            Self::range_rec(&node.left, min, max, out);
+        }
+        if max.coord(node.axis) >= c {
+            Self::range_rec(&node.right, min, max, out);
+        }
+    }
+}
+
+impl<P: KdPoint> Default for KdTree<P> {
+    fn default() -> Self {
+        KdTree::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{KdPoint, KdTree};
+    use crate::smart_pointer::Point;
+    use crate::test_util::Rng;
+
+    fn sorted(mut points: Vec<Point>) -> Vec<Point> {
+        points.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+        points
+    }
+
+    fn brute_force_k_nearest(points: &[Point], target: &Point, k: usize) -> Vec<f64> {
+        let mut dists: Vec<f64> = points.iter().map(|p| p.dist2(target)).collect();
+        dists.sort_by(f64::total_cmp);
+        dists.truncate(k);
+        dists
+    }
+
+    #[test]
+    fn test_empty_and_insert() {
+        let mut tree = KdTree::new();
+        assert!(tree.is_empty());
+        assert_eq!(tree.nearest(&Point::new(0.0, 0.0)), None);
+        tree.insert(Point::new(1.0, 1.0));
+        tree.insert(Point::new(-1.0, 2.0));
+        tree.insert(Point::new(3.0, -4.0));
+        assert_eq!(tree.len(), 3);
+        assert_eq!(
+            tree.nearest(&Point::new(2.0, -3.0)),
+            Some(Point::new(3.0, -4.0))
+        );
+        assert!(tree.k_nearest(&Point::new(0.0, 0.0), 0).is_empty());
+        assert_eq!(tree.k_nearest(&Point::new(0.0, 0.0), 10).len(), 3);
+    }
+
+    #[test]
+    fn test_queries_match_brute_force() {
+        let mut rng = Rng(7);
+        for round in 0..60 {
+            let n = round * 7;
+            let grid = if round % 2 == 0 { Some(20) } else { None };
+            let points = rng.cloud(n, grid);
+
+            // 三分之一逐个 insert，其余用 build
+            let tree = if round % 3 == 0 {
+                let mut tree = KdTree::new();
+                points.iter().for_each(|&p| tree.insert(p));
+                tree
+            } else {
+                KdTree::build(points.clone())
+            };
+            assert_eq!(tree.len(), n);
+
+            for query in rng.cloud(20, grid) {
+                let k = 1 + (rng.next() % 8) as usize;
+                let found: Vec<f64> = tree
+                    .k_nearest(&query, k)
+                    .iter()
+                    .map(|p| p.dist2(&query))
+                    .collect();
+                assert_eq!(found, brute_force_k_nearest(&points, &query, k));
+                assert_eq!(
+                    tree.nearest(&query).map(|p| p.dist2(&query)),
+                    brute_force_k_nearest(&points, &query, 1).first().copied()
+                );
+
+                let other = rng.cloud(1, grid)[0];
+                let min = Point::new(query.x.min(other.x), query.y.min(other.y));
+                let max = Point::new(query.x.max(other.x), query.y.max(other.y));
+                let expected: Vec<Point> = points
+                    .iter()
+                    .copied()
+                    .filter(|p| p.x >= min.x && p.x <= max.x && p.y >= min.y && p.y <= max.y)
+                    .collect();
+                assert_eq!(sorted(tree.range(&min, &max)), sorted(expected));
+            }
+        }
+    }
+}
@@ -0,0 +1,390 @@
+//! BTreeMap 上的键集分页（keyset pagination）
+//! 按偏移量分页（第 n 页跳过 (n - 1) * limit 条）时，如果两次请求之间有插入或删除，
+//! 后面的元素整体移动，就会出现重复或漏掉的元素。键集分页改为记住上一页最后一个键，
+//! 下一页从严格大于（降序时严格小于）这个键的位置开始，用 BTreeMap::range 直接定位，
+//! 所以其他位置的插入和删除不会影响后续页，即使最后一个键本身被删除也没有问题。
+//!
+//! 游标对调用者是不透明的字符串：版本号、排序方向和最后一个键的字节，
+//! 用不带填充的 URL 安全 base64 编码，可以直接放进 URL 查询参数中。
+use std::collections::BTreeMap;
+use std::convert::TryInto;
+use std::error::Error;
+use std::fmt;
+use std::num::NonZeroUsize;
+use std::ops::Bound;
+
+const VERSION: u8 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Asc,
+    Desc,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CursorError {
+    InvalidBase64,
+    /// 游标为空或者缺少必要的字节
+    Truncated,
+    UnsupportedVersion(u8),
+    InvalidDirection(u8),
+    /// 键的字节无法还原成键的类型，比如长度不对或者不是 UTF-8
+    InvalidKey,
+}
+
+impl fmt::Display for CursorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CursorError::InvalidBase64 => write!(f, "cursor is not valid base64"),
+            CursorError::Truncated => write!(f, "cursor is truncated"),
+            CursorError::UnsupportedVersion(v) => write!(f, "unsupported cursor version {}", v),
+            CursorError::InvalidDirection(d) => write!(f, "invalid sort direction {}", d),
+            CursorError::InvalidKey => write!(f, "cursor key does not match the key type"),
+        }
+    }
+}
+
+impl Error for CursorError {}
+
+/// 可以写进游标的键
+pub trait CursorKey: Ord + Sized {
+    fn to_bytes(&self) -> Vec<u8>;
+    fn from_bytes(bytes: &[u8]) -> Option<Self>;
+}
+
+macro_rules! int_cursor_key {
+    ($($t:ty),*) => {
+        $(
+            impl CursorKey for $t {
+                fn to_bytes(&self) -> Vec<u8> {
+                    self.to_be_bytes().to_vec()
+                }
+
+                fn from_bytes(bytes: &[u8]) -> Option<Self> {
+                    Some(<$t>::from_be_bytes(bytes.try_into().ok()?))
+                }
+            }
+        )*
+    };
+}
+
+int_cursor_key!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+impl CursorKey for String {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        String::from_utf8(bytes.to_vec()).ok()
+    }
+}
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// URL 安全、不带填充的 base64 编码：每 3 个字节变成 4 个字符，最后不足 3 个字节时输出 2 或 3 个字符
+fn base64_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let mut buf = [0u8; 3];
+        buf[..chunk.len()].copy_from_slice(chunk);
+        let n = (buf[0] as u32) << 16 | (buf[1] as u32) << 8 | buf[2] as u32;
+        for i in 0..=chunk.len() {
+            out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+        }
+    }
+    out
+}
+
+fn base64_decode(s: &str) -> Option<Vec<u8>> {
+    let value = |c: u8| ALPHABET.iter().position(|&a| a == c).map(|v| v as u32);
+    let mut out = Vec::with_capacity(s.len() / 4 * 3 + 2);
+    for chunk in s.as_bytes().chunks(4) {
+        // 一个字符只有 6 位，凑不出一个字节
+        if chunk.len() == 1 {
+            return None;
+        }
+        let mut n = 0;
+        for (i, &c) in chunk.iter().enumerate() {
+            n |= value(c)? << (18 - 6 * i);
+        }
+        let bytes = [(n >> 16) as u8, (n >> 8) as u8, n as u8];
+        let len = chunk.len() - 1;
+        // 没有用到的低位必须是 0，保证每个字节序列只有一种编码
+        if bytes[len..].iter().any(|&b| b != 0) {
+            return None;
+        }
+        out.extend_from_slice(&bytes[..len]);
+    }
+    Some(out)
+}
+
+/// 已经取到的最后一个键和排序方向
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cursor<K> {
+    pub last: K,
+    pub direction: Direction,
+}
+
+impl<K: CursorKey> Cursor<K> {
+    pub fn encode(&self) -> String {
+        let direction = match self.direction {
+            Direction::Asc => 0,
+            Direction::Desc => 1,
+        };
+        let mut bytes = vec![VERSION, direction];
+        bytes.extend(self.last.to_bytes());
+        base64_encode(&bytes)
+    }
+
+    pub fn decode(token: &str) -> Result<Self, CursorError> {
+        let bytes = base64_decode(token).ok_or(CursorError::InvalidBase64)?;
+        match bytes.as_slice() {
+            [] | [_] => Err(CursorError::Truncated),
+            &[version, ..] if version != VERSION => Err(CursorError::UnsupportedVersion(version)),
+            [_, direction, key @ ..] => {
+                let direction = match direction {
+                    0 => Direction::Asc,
+                    1 => Direction::Desc,
+                    &d => return Err(CursorError::InvalidDirection(d)),
+                };
+                let last = K::from_bytes(key).ok_or(CursorError::InvalidKey)?;
+                Ok(Cursor { last, direction })
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct KeysetPage<'a, K, V> {
+    pub items: Vec<(&'a K, &'a V)>,
+    /// 后面还有元素时才有下一页的游标
+    pub next: Option<String>,
+}
+
+/// 从 iter 中取出最多 limit 个元素；多看一个元素，用来判断是否还有下一页
+fn collect<'a, K: CursorKey + Clone + 'a, V: 'a>(
+    mut iter: impl Iterator<Item = (&'a K, &'a V)>,
+    limit: NonZeroUsize,
+    direction: Direction,
+) -> KeysetPage<'a, K, V> {
+    let items: Vec<_> = iter.by_ref().take(limit.get()).collect();
+    let next = match (iter.next(), items.last()) {
+        (Some(_), Some(&(last, _))) => Some(
+            Cursor {
+                last: last.clone(),
+                direction,
+            }
+            .encode(),
+        ),
+        _ => None,
+    };
+    KeysetPage { items, next }
+}
+
+/// 第一页，每页条数通常来自客户端，用 NonZeroUsize 让调用者在解析请求时就拒绝 0
+pub fn first_page<K: CursorKey + Clone, V>(
+    map: &BTreeMap<K, V>,
+    direction: Direction,
+    limit: NonZeroUsize,
+) -> KeysetPage<'_, K, V> {
+    match direction {
+        Direction::Asc => collect(map.iter(), limit, direction),
+        Direction::Desc => collect(map.iter().rev(), limit, direction),
+    }
+}
+
+/// 游标之后的一页，排序方向由游标决定
+pub fn next_page<'a, K: CursorKey + Clone, V>(
+    map: &'a BTreeMap<K, V>,
+    token: &str,
+    limit: NonZeroUsize,
+) -> Result<KeysetPage<'a, K, V>, CursorError> {
+    let cursor = Cursor::<K>::decode(token)?;
+    let after = Bound::Excluded(&cursor.last);
+    Ok(match cursor.direction {
+        Direction::Asc => collect(
+            map.range((after, Bound::Unbounded)),
+            limit,
+            cursor.direction,
+        ),
+        Direction::Desc => collect(
+            map.range((Bound::Unbounded, after)).rev(),
+            limit,
+            cursor.direction,
+        ),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        base64_decode, base64_encode, first_page, next_page, Cursor, CursorError, Direction,
+    };
+    use crate::test_util::Rng;
+    use std::collections::{BTreeMap, BTreeSet};
+    use std::num::NonZeroUsize;
+
+    fn limit(n: usize) -> NonZeroUsize {
+        NonZeroUsize::new(n).unwrap()
+    }
+
+    #[test]
+    fn test_base64() {
+        let cases: [(&[u8], &str); 6] = [
+            (b"", ""),
+            (b"f", "Zg"),
+            (b"fo", "Zm8"),
+            (b"foo", "Zm9v"),
+            (b"foob", "Zm9vYg"),
+            (&[0xfb, 0xff, 0xfe], "-__-"),
+        ];
+        for &(bytes, text) in &cases {
+            assert_eq!(base64_encode(bytes), text);
+            assert_eq!(base64_decode(text).as_deref(), Some(bytes));
+        }
+        let mut rng = Rng(0x5eed);
+        for len in 0..40 {
+            let bytes: Vec<u8> = (0..len).map(|_| rng.next() as u8).collect();
+            assert_eq!(base64_decode(&base64_encode(&bytes)), Some(bytes));
+        }
+        assert_eq!(base64_decode("Z"), None);
+        assert_eq!(base64_decode("Zm9v+"), None);
+        // 最后一个字符的低位不为 0
+        assert_eq!(base64_decode("Zh"), None);
+    }
+
+    #[test]
+    fn test_cursor_token() {
+        let cursor = Cursor {
+            last: 42u32,
+            direction: Direction::Desc,
+        };
+        let token = cursor.encode();
+        assert_eq!(token, "AQEAAAAq");
+        assert_eq!(Cursor::decode(&token), Ok(cursor));
+
+        let cursor = Cursor {
+            last: "chapter 7".to_string(),
+            direction: Direction::Asc,
+        };
+        assert_eq!(Cursor::decode(&cursor.encode()), Ok(cursor));
+
+        assert_eq!(
+            Cursor::<u32>::decode("not base64!"),
+            Err(CursorError::InvalidBase64)
+        );
+        assert_eq!(Cursor::<u32>::decode(""), Err(CursorError::Truncated));
+        assert_eq!(
+            Cursor::<u32>::decode(&base64_encode(&[2, 0, 0, 0, 0, 1])),
+            Err(CursorError::UnsupportedVersion(2))
+        );
+        assert_eq!(
+            Cursor::<u32>::decode(&base64_encode(&[1, 7, 0, 0, 0, 1])),
+            Err(CursorError::InvalidDirection(7))
+        );
+        // u32 的游标不能当作 u64 的游标使用
+        assert_eq!(
+            Cursor::<u64>::decode("AQEAAAAq"),
+            Err(CursorError::InvalidKey)
+        );
+        assert_eq!(
+            Cursor::<String>::decode(&base64_encode(&[1, 0, 0xff])),
+            Err(CursorError::InvalidKey)
+        );
+    }
+
+    #[test]
+    fn test_pages() {
+        let map: BTreeMap<i32, char> = (0..10).map(|i| (i, (b'a' + i as u8) as char)).collect();
+        let page = first_page(&map, Direction::Asc, limit(4));
+        assert_eq!(
+            page.items,
+            vec![(&0, &'a'), (&1, &'b'), (&2, &'c'), (&3, &'d')]
+        );
+        let page = next_page(&map, &page.next.unwrap(), limit(4)).unwrap();
+        assert_eq!(page.items.first(), Some(&(&4, &'e')));
+        let page = next_page(&map, &page.next.unwrap(), limit(4)).unwrap();
+        assert_eq!(page.items, vec![(&8, &'i'), (&9, &'j')]);
+        assert_eq!(page.next, None);
+
+        // 恰好取完时没有下一页，不会返回一个指向空页的游标
+        let page = first_page(&map, Direction::Desc, limit(5));
+        let page = next_page(&map, &page.next.unwrap(), limit(5)).unwrap();
+        let keys: Vec<i32> = page.items.iter().map(|&(&k, _)| k).collect();
+        assert_eq!(keys, vec![4, 3, 2, 1, 0]);
+        assert_eq!(page.next, None);
+
+        let empty = BTreeMap::<i32, char>::new();
+        assert_eq!(first_page(&empty, Direction::Asc, limit(3)).items, vec![]);
+    }
+
+    /// 每取一页就随机插入和删除一些键：从头到尾都存在的键必须恰好出现一次，
+    /// 所有取到的键严格单调，不会重复
+    fn walk_with_churn(direction: Direction, seed: u64) {
+        let mut rng = Rng(seed);
+        let mut map: BTreeMap<u64, u64> = (0..200).map(|k| (k * 10, k)).collect();
+        let mut stable: BTreeSet<u64> = map.keys().copied().collect();
+        let mut seen = Vec::new();
+
+        let mut page = first_page(&map, direction, limit(7));
+        loop {
+            seen.extend(page.items.iter().map(|&(&k, _)| k));
+            let token = match page.next {
+                Some(token) => token,
+                None => break,
+            };
+            for _ in 0..5 {
+                let key = rng.next() % 2100;
+                if rng.next() % 2 == 0 {
+                    map.insert(key, 0);
+                } else if map.remove(&key).is_some() {
+                    stable.remove(&key);
+                }
+            }
+            // 有时直接删掉游标指向的键
+            if rng.next() % 4 == 0 {
+                let last = *seen.last().unwrap();
+                map.remove(&last);
+                stable.remove(&last);
+            }
+            page = next_page(&map, &token, limit(7)).unwrap();
+        }
+
+        let ordered = seen.windows(2).all(|w| match direction {
+            Direction::Asc => w[0] < w[1],
+            Direction::Desc => w[0] > w[1],
+        });
+        assert!(ordered, "keys repeated or out of order: {:?}", seen);
+        let seen: BTreeSet<u64> = seen.into_iter().collect();
+        assert!(
+            stable.is_subset(&seen),
+            "missed {:?}",
+            stable.difference(&seen)
+        );
+    }
+
+    #[test]
+    fn test_stable_under_concurrent_changes() {
+        for seed in 1..20 {
+            walk_with_churn(Direction::Asc, seed * 7919);
+            walk_with_churn(Direction::Desc, seed * 104_729);
+        }
+    }
+
+    #[test]
+    fn test_string_keys() {
+        let mut map = BTreeMap::new();
+        for word in &["pear", "apple", "fig", "kiwi", "banana"] {
+            map.insert(word.to_string(), word.len());
+        }
+        let page = first_page(&map, Direction::Asc, limit(2));
+        assert_eq!(page.items.len(), 2);
+        let token = page.next.unwrap();
+        // 两次请求之间插入了排在前面的键，下一页不受影响
+        map.insert("avocado".to_string(), 7);
+        let page = next_page(&map, &token, limit(2)).unwrap();
+        let keys: Vec<&str> = page.items.iter().map(|(k, _)| k.as_str()).collect();
+        assert_eq!(keys, vec!["fig", "kiwi"]);
+    }
+}
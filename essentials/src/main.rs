@@ -23,8 +23,31 @@
 /// 所以在while里面直接返回类型是不被编译期捕捉到的，因为编译器认为while块可能进入也可能不进入
 /// 这是因为受到了CTFE功能的限制。如果需要使用无限循环，需要使用loop循环。
 ///
+mod algorithms;
+mod calc;
+mod cidr;
 mod collections;
+mod color;
+mod decimal;
+mod fizzbuzz;
+mod geometry;
+mod ip;
+mod kdtree;
+mod keyset;
+mod matrix;
+mod ordered;
+mod people;
 mod smart_pointer;
+#[cfg(test)]
+mod test_util;
+
+use cidr::Cidr;
+use color::{Color, Color1};
+use decimal::Decimal;
+use fizzbuzz::FizzBuzzRules;
+use ip::IpAddr;
+use people::{Gender, People};
+use smart_pointer::Point;
 
 pub fn answer() -> () {
     // let创建的变量一般称为绑定binding，
@@ -79,18 +102,9 @@ fn reference() {
     assert_eq!(42, *e);
 }
 
+// 经典的 3/5/15 规则现在只是 FizzBuzzRules 的默认规则集
 fn fizz_buzz(x: i32) -> String {
-    if x % 15 == 0 {
-        return String::from("fizzbuzz");
-    }
-    if x % 3 == 0 {
-        return String::from("fizz");
-    }
-    if x % 5 == 0 {
-        return String::from("buzz");
-    }
-
-    x.to_string()
+    FizzBuzzRules::default().apply(x)
 }
 
 fn lifetimes() {
@@ -136,12 +150,46 @@ fn main() {
     assert_eq!(fizz_buzz(3), "fizz".to_string());
     assert_eq!(fizz_buzz(5), "buzz".to_string());
     assert_eq!(fizz_buzz(13), "13".to_string());
+    // 自定义规则集：同时命中多条规则时只取第一条
+    let rules = FizzBuzzRules::new()
+        .divisor(3, "fizz")
+        .predicate(|x| x % 7 == 0, "bazz")
+        .policy(fizzbuzz::Policy::First);
+    let lines: Vec<String> = rules.iter(20..=22).map(|line| line.to_string()).collect();
+    assert_eq!(lines, ["20", "fizz", "22"]);
     lifetimes();
 
     let a = 2;
     let b = 3;
     assert_eq!(math(sum, a, b), 5);
     assert_eq!(math(product, a, b), 6);
+    // 计算器同样通过函数指针调用运算符，溢出和除以零作为错误返回
+    assert_eq!(calc::eval("(2 + 3) * 4 ^ 2"), Ok(80));
+    assert_eq!(calc::eval("1 / 0"), Err(calc::CalcError::DivisionByZero));
+    // 运算符表也可以修改，比如把乘方换成 **
+    let mut ops = calc::Operators::default();
+    let pow = ops.unregister("^").unwrap();
+    ops.register("**", pow);
+    // 自定义运算符的 check 要拒绝所有会让函数指针 panic 的输入
+    let shl = calc::Operator::new(
+        3,
+        calc::Assoc::Left,
+        |a, b| a << b,
+        |a, b| match b {
+            0..=31 => Ok(()),
+            _ => Err(calc::CalcError::Overflow {
+                op: "<<".to_string(),
+                lhs: a,
+                rhs: b,
+            }),
+        },
+    );
+    ops.register("<<", shl);
+    let mut calculator = calc::Calculator::with_operators(ops);
+    assert_eq!(calculator.eval_line("x = 2 ** 10"), Ok(Some(1024)));
+    assert_eq!(calculator.vars().get("x"), Some(&1024));
+    assert_eq!(calculator.eval_line("x << 2"), Ok(Some(4096)));
+    assert!(calculator.eval_line("1 << 32").is_err());
 
     assert_eq!(true_maker()(), true);
 
@@ -150,6 +198,23 @@ fn main() {
     assert_eq!(arr.len(), 3 * 2);
     let arr = [0; init_len(6)];
     assert_eq!(arr.len(), 6 * 2);
+    assert_eq!(arr.size(), 12);
+    // 矩阵的维度同样是 const 泛型参数，(2x3) * (3x2) 得到 2x2 矩阵
+    let m = matrix::Matrix([[1, 2, 3], [4, 5, 6]]);
+    let mm: matrix::Square<i32, 2> = m * m.transpose();
+    assert_eq!(mm, matrix::Matrix([[14, 32], [32, 77]]));
+    assert_eq!(mm.determinant(), 54);
+    assert_eq!(
+        m.map(|x| x % 2),
+        matrix::Matrix::from_fn(|r, c| (r + c + 1) as i32 % 2)
+    );
+    assert_eq!(m + m - m * 2, matrix::Matrix::zeros());
+    let inv = mm.map(f64::from).inverse().unwrap();
+    assert!(((inv * mm.map(f64::from))[(1, 1)] - 1.0).abs() < 1e-12);
+    assert_eq!(
+        matrix::Square::<f64, 2>::identity().inverse(),
+        Some(matrix::Square::identity())
+    );
 
     // closure can capture environment vars which function can not.
     let out = 42;
@@ -173,6 +238,128 @@ fn main() {
     flow_control();
     match_expr();
     primitive();
+    plane_geometry();
+    money();
+    keyset_pages();
+}
+
+// 键集分页：下一页从上一页最后一个键之后开始，中间插入的数据不会造成重复或遗漏
+fn keyset_pages() {
+    use keyset::{first_page, next_page, Cursor, CursorError, Direction};
+    use std::collections::BTreeMap;
+    use std::num::NonZeroUsize;
+
+    let limit = NonZeroUsize::new(2).unwrap();
+    let mut users: BTreeMap<u32, &str> = BTreeMap::new();
+    users.extend([(1, "ann"), (3, "bob"), (5, "cat"), (7, "dan")]);
+    let page = first_page(&users, Direction::Asc, limit);
+    let names: Vec<&str> = page.items.iter().map(|&(_, &name)| name).collect();
+    assert_eq!(names, ["ann", "bob"]);
+    let token = page.next.unwrap();
+    assert_eq!(
+        Cursor::<u32>::decode(&token),
+        Ok(Cursor {
+            last: 3,
+            direction: Direction::Asc
+        })
+    );
+
+    users.insert(2, "eve");
+    let page = next_page(&users, &token, limit).unwrap();
+    let keys: Vec<u32> = page.items.iter().map(|&(&k, _)| k).collect();
+    assert_eq!(keys, [5, 7]);
+    assert_eq!(page.next, None);
+    assert_eq!(
+        next_page(&users, "!", limit).err(),
+        Some(CursorError::InvalidBase64)
+    );
+}
+
+// 金额计算：定点十进制数的舍入方式和溢出处理
+fn money() {
+    use decimal::Rounding;
+    type Money = Decimal<2>;
+    let price: Money = "19.99".parse().unwrap();
+    let qty = Money::from_int(3).unwrap();
+    let total = price * qty;
+    assert_eq!(total.raw(), 5997);
+    assert_eq!(total.trunc(), 59);
+    assert_eq!(total.saturating_div(qty, Rounding::HalfEven), price);
+    assert_eq!(Money::from_raw(1).to_string(), "0.01");
+
+    let x: Decimal<3> = "2.345".parse().unwrap();
+    assert_eq!(x.round(2, Rounding::HalfEven).to_string(), "2.340");
+    assert_eq!(x.round(2, Rounding::HalfUp).to_string(), "2.350");
+    assert_eq!(x.rescale::<2>(Rounding::Truncate), "2.34".parse().ok());
+
+    let refund = Money::ZERO - total;
+    assert!(refund.is_negative());
+    assert_eq!(refund.abs(), total);
+    // 边界值上的运算返回 None 或者饱和，而不是 panic
+    assert_eq!(Money::MIN.checked_abs(), None);
+    assert_eq!(Money::MAX.checked_round(1, Rounding::HalfUp), None);
+    assert_eq!(Money::MAX.saturating_add(Money::ONE), Money::MAX);
+    assert_eq!(Money::MIN.saturating_sub(Money::ONE), Money::MIN);
+    assert_eq!(
+        Money::MAX.saturating_mul(qty, Rounding::HalfEven),
+        Money::MAX
+    );
+}
+
+// 平面几何：线段求交、多边形面积以及点和多边形的位置关系
+fn plane_geometry() {
+    use geometry::{Intersection, Location, Polygon, Segment};
+    let p = Point::new;
+    let (o, a) = (p(0.0, 0.0), p(3.0, 4.0));
+    assert_eq!(o.distance(a), 5.0);
+    assert_eq!(a.norm(), 5.0);
+    assert_eq!(p(1.0, 0.0).cross(p(0.0, 1.0)), 1.0);
+    assert_eq!(Segment::new(o, a).length(), 5.0);
+
+    let diagonal = Segment::new(p(0.0, 0.0), p(4.0, 4.0));
+    let other = Segment::new(p(0.0, 4.0), p(4.0, 0.0));
+    assert!(diagonal.intersects(&other));
+    assert_eq!(
+        diagonal.intersection(&other),
+        Some(Intersection::Point(p(2.0, 2.0)))
+    );
+    assert!(diagonal.contains(p(1.0, 1.0)));
+
+    let square = Polygon::new(vec![p(0.0, 0.0), p(4.0, 0.0), p(4.0, 4.0), p(0.0, 4.0)]);
+    assert_eq!(square.signed_area(), 16.0);
+    assert_eq!(square.area(), 16.0);
+    assert_eq!(square.centroid(), Some(p(2.0, 2.0)));
+    assert_eq!(square.edges().count(), 4);
+    assert_eq!(square.locate(p(4.0, 1.0)), Location::Boundary);
+    assert!(square.contains(p(1.0, 1.0)) && !square.contains(p(5.0, 1.0)));
+
+    // 凸包只保留最外层的顶点，内部点和边上的共线点都会被去掉
+    let mut points = square.vertices.clone();
+    points.extend([p(1.0, 3.0), p(2.0, 0.0), p(3.5, 3.9)]);
+    let hull = algorithms::convex_hull(&points);
+    assert_eq!(hull, square.vertices);
+    assert_eq!(algorithms::graham_scan(&points), hull);
+    assert_eq!(
+        algorithms::closest_pair(&points),
+        Some((p(3.5, 3.9), p(4.0, 4.0)))
+    );
+
+    // k-d 树上的最近邻和矩形范围查询
+    let mut tree = kdtree::KdTree::build(points);
+    assert!(!tree.is_empty());
+    tree.insert(p(10.0, 10.0));
+    assert_eq!(tree.len(), 8);
+    assert_eq!(tree.nearest(&p(9.0, 8.0)), Some(p(10.0, 10.0)));
+    assert_eq!(tree.k_nearest(&o, 2), vec![o, p(2.0, 0.0)]);
+    let mut inside = tree.range(&p(0.0, 3.0), &p(4.0, 4.0));
+    inside.sort();
+    assert_eq!(
+        inside,
+        vec![p(0.0, 4.0), p(1.0, 3.0), p(3.5, 3.9), p(4.0, 4.0)]
+    );
+    let mut empty = kdtree::KdTree::new();
+    empty.insert(o);
+    assert_eq!(empty.nearest(&a), Some(o));
 }
 
 fn primitive() {
@@ -203,6 +390,10 @@ fn primitive() {
     println!("{:?}", std::f32::NAN);
     println!("{:?}", std::f32::MIN);
     println!("{:?}", std::f32::MAX);
+    // 二进制浮点数无法精确表示 0.1，金额计算使用十进制定点数
+    assert_ne!(0.1 + 0.2, 0.3);
+    let price: Decimal<2> = "0.10".parse().unwrap();
+    assert_eq!((price + "0.20".parse().unwrap()).to_string(), "0.30");
 
     // char
     // Unicode标量值 每个字符占4个字节
@@ -312,15 +503,27 @@ fn primitive() {
     let (x, y) = result;
     assert_eq!(x, 1);
 
-    let alex = People::new("Alex", 1);
+    let alex = People::builder().name("Alex").gender(Gender::Male).build();
     // 面对对象消息通信模型receiver.message
     println!("name: {:?}", alex.name());
-    alex.gender();
+    println!("gender: {}", alex.gender());
+    // 两个字段的设置顺序可以任意，构建结果与直接调用 new 相同
+    let sam = People::builder().gender(Gender::Female).name("Sam").build();
+    assert_eq!(sam, People::new("Sam", Gender::Female));
 
     let color = Color(10, 20, 30);
     assert_eq!(color.0, 10);
     assert_eq!(color.1, 20);
     assert_eq!(color.2, 30);
+    assert_eq!(color.to_string(), "#0a141e");
+    // 颜色空间之间的往返转换不会改变颜色
+    assert_eq!(Color::from_hsl(color.to_hsl()), color);
+    assert_eq!(Color::from_hsv(color.to_hsv()), color);
+    assert_eq!(Color::from_lab(color.to_lab()), color);
+    let red = Color::from(Color1::Red);
+    assert_eq!(red.blend(Color1::Blue.into(), 0.5).to_string(), "#800080");
+    assert!(red.delta_e(Color(250, 0, 0)) < red.delta_e(Color1::Green.into()));
+    assert_eq!(std::convert::TryFrom::try_from(red), Ok(Color1::Red));
 
     let int = Integer(10);
     assert_eq!(int.0, 10);
@@ -337,6 +540,37 @@ fn primitive() {
 
     let x: fn(u8, u8, u8, u8) -> IpAddr = IpAddr::V4; // 函数指针
     let home = IpAddr::V4(127, 0, 0, 1);
+    assert!(home.is_loopback());
+    assert_eq!(Ok(home), "127.0.0.1".parse());
+    assert_eq!(home.octets(), Some([127, 0, 0, 1]));
+    let v6: IpAddr = "fe80::1".parse().unwrap();
+    assert!(v6.is_ipv6() && v6.is_link_local());
+    assert_eq!(v6, IpAddr::from_segments([0xfe80, 0, 0, 0, 0, 0, 0, 1]));
+    let kinds = |s: &str| {
+        let ip: IpAddr = s.parse().unwrap();
+        (ip.is_unspecified(), ip.is_private(), ip.is_multicast())
+    };
+    assert_eq!(kinds("::"), (true, false, false));
+    assert_eq!(kinds("10.1.2.3"), (false, true, false));
+    assert_eq!(kinds("ff02::1"), (false, false, true));
+
+    // 网段：子网划分、主机地址和聚合
+    let net: Cidr = "192.168.0.0/23".parse().unwrap();
+    assert_eq!(net.network(), IpAddr::V4(192, 168, 0, 0));
+    assert_eq!(net.prefix_len(), 23);
+    assert_eq!(net.netmask(), IpAddr::V4(255, 255, 254, 0));
+    assert_eq!(net.last(), IpAddr::V4(192, 168, 1, 255));
+    assert!(net.contains(IpAddr::V4(192, 168, 1, 7)));
+    let halves: Vec<Cidr> = net.subnets(24).unwrap().collect();
+    assert!(halves.iter().all(|half| net.contains_cidr(half)));
+    assert_eq!(halves[1].supernet(), Some(net));
+    assert_eq!(
+        halves[0].supernet_to(22).unwrap().to_string(),
+        "192.168.0.0/22"
+    );
+    assert_eq!(Cidr::aggregate(&halves), vec![net]);
+    assert_eq!(halves[0].hosts().count(), 254);
+    assert_eq!(Cidr::new(v6, 129), Err(cidr::CidrError::InvalidPrefix));
 }
 
 enum Number {
@@ -345,26 +579,11 @@ enum Number {
     Two,
 }
 
-// c-like enum
-enum Color1 {
-    Red = 0xff0000,
-    Green = 0x00ff00,
-    Blue = 0x0000ff,
-}
-
-enum IpAddr {
-    // 携带了参数的枚举值，本质上属于函数指针类型
-    V4(u8, u8, u8, u8),
-    V6(String),
-}
-
 enum OptionInt {
     Some(i32),
     None,
 }
 
-// Tuple-Like struct, file no named
-struct Color(i32, i32, i32);
 // 当元组结构体只有一个字段的时候，我们称之为New Type模式
 struct Integer(u32);
 // 可以使用type关键字为一个类型创建别名
@@ -373,28 +592,6 @@ type Int = i32;
 struct Empty;
 struct Empty1 {}
 
-// Named-Field struct
-#[derive(Debug, PartialEq)]
-struct People {
-    name: &'static str,
-    gender: u32,
-}
-
-impl People {
-    fn new(name: &'static str, gender: u32) -> Self {
-        People { name, gender }
-    }
-
-    fn name(&self) -> &'static str {
-        self.name
-    }
-
-    fn gender(&self) {
-        let gender = if self.gender == 1 { "boy" } else { "girl" };
-        println!("gender: {:?}", gender);
-    }
-}
-
 fn move_coords(x: (i32, i32)) -> (i32, i32) {
     (x.0 + 1, x.1 + 1)
 }
@@ -493,6 +690,14 @@ const fn init_len(n: usize) -> usize {
     return n * 2;
 }
 
-// trait Foo {}
-// const generics is experimental
-// impl<T, const N: usize> Foo for [T; N] {}
+// const generics 已经稳定（Rust 1.51），可以为任意长度的数组实现trait，
+// 基于 [[T; C]; R] 的 Matrix<T, R, C> 见 matrix 模块
+trait Foo {
+    fn size(&self) -> usize;
+}
+
+impl<T, const N: usize> Foo for [T; N] {
+    fn size(&self) -> usize {
+        N
+    }
+}
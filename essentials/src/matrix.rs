@@ -0,0 +1,315 @@
+//! 基于 const 泛型的矩阵
+//! Matrix<T, R, C> 的行数和列数是类型的一部分，矩阵乘法要求左边的列数等于右边的行数，
+//! 维度不匹配的乘法在编译期就会报错，而不是在运行时 panic。
+use std::ops::{Add, Div, Index, IndexMut, Mul, Neg, Sub};
+
+/// 矩阵元素需要的最小数字约束：加法、乘法以及零元和单位元
+pub trait Numeric:
+    Copy + PartialEq + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self>
+{
+    const ZERO: Self;
+    const ONE: Self;
+}
+
+macro_rules! impl_numeric {
+    ($zero:expr, $one:expr; $($t:ty)*) => {
+        $(impl Numeric for $t {
+            const ZERO: Self = $zero;
+            const ONE: Self = $one;
+        })*
+    };
+}
+
+impl_numeric!(0, 1; i8 i16 i32 i64 i128 isize u8 u16 u32 u64 u128 usize);
+impl_numeric!(0.0, 1.0; f32 f64);
+
+/// 浮点元素：除法封闭，并且带有判断舍入误差所需的机器精度
+pub trait Float: Numeric + Div<Output = Self> + PartialOrd {
+    const EPSILON: Self;
+
+    fn abs(self) -> Self;
+    fn from_usize(n: usize) -> Self;
+}
+
+macro_rules! impl_float {
+    ($($t:ident)*) => {
+        $(impl Float for $t {
+            const EPSILON: Self = $t::EPSILON;
+
+            fn abs(self) -> Self {
+                $t::abs(self)
+            }
+
+            fn from_usize(n: usize) -> Self {
+                n as $t
+            }
+        })*
+    };
+}
+
+impl_float!(f32 f64);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Matrix<T, const R: usize, const C: usize>(pub [[T; C]; R]);
+
+/// 方阵
+pub type Square<T, const N: usize> = Matrix<T, N, N>;
+
+impl<T: Copy, const R: usize, const C: usize> Matrix<T, R, C> {
+    pub fn from_fn(mut f: impl FnMut(usize, usize) -> T) -> Self {
+        Matrix(std::array::from_fn(|r| std::array::from_fn(|c| f(r, c))))
+    }
+
+    pub fn transpose(&self) -> Matrix<T, C, R> {
+        Matrix::from_fn(|r, c| self.0[c][r])
+    }
+
+    pub fn map<U: Copy>(&self, mut f: impl FnMut(T) -> U) -> Matrix<U, R, C> {
+        Matrix::from_fn(|r, c| f(self.0[r][c]))
+    }
+}
+
+impl<T: Numeric, const R: usize, const C: usize> Matrix<T, R, C> {
+    pub fn zeros() -> Self {
+        Matrix([[T::ZERO; C]; R])
+    }
+}
+
+impl<T: Numeric, const N: usize> Square<T, N> {
+    pub fn identity() -> Self {
+        Matrix::from_fn(|r, c| if r == c { T::ONE } else { T::ZERO })
+    }
+}
+
+impl<T: Numeric + Div<Output = T>, const N: usize> Square<T, N> {
+    /// Bareiss 无分数消元法：每一步的除法都是整除，所以对整数矩阵也能得到精确的行列式
+    pub fn determinant(&self) -> T {
+        if N == 0 {
+            return T::ONE;
+        }
+        let mut m = self.0;
+        let mut negate = false;
+        let mut prev = T::ONE;
+        for k in 0..N - 1 {
+            if m[k][k] == T::ZERO {
+                match (k + 1..N).find(|&i| m[i][k] != T::ZERO) {
+                    Some(i) => {
+                        m.swap(i, k);
+                        negate = !negate;
+                    }
+                    None => return T::ZERO,
+                }
+            }
+            for i in k + 1..N {
+                for j in k + 1..N {
+                    m[i][j] = (m[i][j] * m[k][k] - m[i][k] * m[k][j]) / prev;
+                }
+            }
+            prev = m[k][k];
+        }
+        if negate {
+            T::ZERO - m[N - 1][N - 1]
+        } else {
+            m[N - 1][N - 1]
+        }
+    }
+}
+
+impl<T: Float, const N: usize> Square<T, N> {
+    /// 按比例选主元的高斯-约当消元，奇异矩阵返回 None
+    /// 舍入误差会让奇异矩阵的主元变成一个极小的非零值，所以主元的绝对值不超过
+    /// EPSILON * 所在行原本的最大元素绝对值 * N 时就视为零。容差按行缩放，
+    /// 各行量级相差很大的可逆矩阵（比如 diag(1e10, 1e-10)）不会被误判为奇异
+    pub fn inverse(&self) -> Option<Self> {
+        let mut scale = [T::ZERO; N];
+        for (s, row) in scale.iter_mut().zip(&self.0) {
+            *s = row
+                .iter()
+                .fold(T::ZERO, |max, &x| if x.abs() > max { x.abs() } else { max });
+            if *s == T::ZERO {
+                return None;
+            }
+        }
+        let tolerance = T::EPSILON * T::from_usize(N);
+        let mut m = self.0;
+        let mut inv = Self::identity().0;
+        for k in 0..N {
+            let pivot = (k..N).max_by(|&a, &b| {
+                (m[a][k].abs() / scale[a])
+                    .partial_cmp(&(m[b][k].abs() / scale[b]))
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })?;
+            if m[pivot][k].abs() <= tolerance * scale[pivot] {
+                return None;
+            }
+            scale.swap(pivot, k);
+            m.swap(pivot, k);
+            inv.swap(pivot, k);
+
+            let p = m[k][k];
+            for j in 0..N {
+                m[k][j] = m[k][j] / p;
+                inv[k][j] = inv[k][j] / p;
+            }
+            for i in (0..N).filter(|&i| i != k) {
+                let factor = m[i][k];
+                if factor == T::ZERO {
+                    continue;
+                }
+                for j in 0..N {
+                    m[i][j] = m[i][j] - factor * m[k][j];
+                    inv[i][j] = inv[i][j] - factor * inv[k][j];
+                }
+            }
+        }
+        Some(Matrix(inv))
+    }
+}
+
+impl<T, const R: usize, const C: usize> Index<(usize, usize)> for Matrix<T, R, C> {
+    type Output = T;
+
+    fn index(&self, (r, c): (usize, usize)) -> &T {
+        &self.0[r][c]
+    }
+}
+
+impl<T, const R: usize, const C: usize> IndexMut<(usize, usize)> for Matrix<T, R, C> {
+    fn index_mut(&mut self, (r, c): (usize, usize)) -> &mut T {
+        &mut self.0[r][c]
+    }
+}
+
+impl<T: Numeric, const R: usize, const C: usize> Add for Matrix<T, R, C> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Matrix::from_fn(|r, c| self.0[r][c] + rhs.0[r][c])
+    }
+}
+
+impl<T: Numeric, const R: usize, const C: usize> Sub for Matrix<T, R, C> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Matrix::from_fn(|r, c| self.0[r][c] - rhs.0[r][c])
+    }
+}
+
+impl<T: Numeric + Neg<Output = T>, const R: usize, const C: usize> Neg for Matrix<T, R, C> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        self.map(|x| -x)
+    }
+}
+
+/// 数乘
+impl<T: Numeric, const R: usize, const C: usize> Mul<T> for Matrix<T, R, C> {
+    type Output = Self;
+
+    fn mul(self, k: T) -> Self {
+        self.map(|x| x * k)
+    }
+}
+
+/// (R x K) * (K x C) = (R x C)，K 不一致时无法通过类型检查
+impl<T: Numeric, const R: usize, const K: usize, const C: usize> Mul<Matrix<T, K, C>>
+    for Matrix<T, R, K>
+{
+    type Output = Matrix<T, R, C>;
+
+    fn mul(self, rhs: Matrix<T, K, C>) -> Matrix<T, R, C> {
+        Matrix::from_fn(|r, c| (0..K).fold(T::ZERO, |acc, k| acc + self.0[r][k] * rhs.0[k][c]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Matrix, Square};
+
+    #[test]
+    fn test_mul_and_transpose() {
+        let a = Matrix([[1, 2, 3], [4, 5, 6]]);
+        let b = Matrix([[7, 8], [9, 10], [11, 12]]);
+        // (2x3) * (3x2) = (2x2)，而 a * a 这样维度不匹配的乘法无法编译
+        let c: Matrix<i32, 2, 2> = a * b;
+        assert_eq!(c, Matrix([[58, 64], [139, 154]]));
+        assert_eq!(b * a, Matrix([[39, 54, 69], [49, 68, 87], [59, 82, 105]]));
+        assert_eq!(a.transpose(), Matrix([[1, 4], [2, 5], [3, 6]]));
+        assert_eq!((a * b).transpose(), b.transpose() * a.transpose());
+        assert_eq!(a * Square::identity(), a);
+        assert_eq!(Square::<i32, 2>::identity() * a, a);
+    }
+
+    #[test]
+    fn test_elementwise() {
+        let a = Matrix([[1.0, -2.0], [0.5, 4.0]]);
+        assert_eq!(a + a, a * 2.0);
+        assert_eq!(a - a, Matrix::zeros());
+        assert_eq!(-a, Matrix([[-1.0, 2.0], [-0.5, -4.0]]));
+        let mut b = a;
+        b[(1, 0)] = 9.0;
+        assert_eq!(b[(1, 0)], 9.0);
+    }
+
+    #[test]
+    fn test_determinant() {
+        assert_eq!(Matrix([[3]]).determinant(), 3);
+        assert_eq!(Matrix([[1, 2], [3, 4]]).determinant(), -2);
+        assert_eq!(
+            Matrix([[2, -3, 1], [2, 0, -1], [1, 4, 5]]).determinant(),
+            49
+        );
+        // 第一个主元为零时需要换行
+        assert_eq!(Matrix([[0, 1, 2], [1, 0, 3], [4, -3, 8]]).determinant(), -2);
+        assert_eq!(Matrix([[1, 2, 3], [4, 5, 6], [7, 8, 9]]).determinant(), 0);
+        assert_eq!(Square::<i64, 5>::identity().determinant(), 1);
+        assert_eq!(Matrix::<i32, 0, 0>([]).determinant(), 1);
+        let d = Matrix([[2.0, 1.0], [1.0, 3.0]]).determinant();
+        assert!((d - 5.0f64).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_inverse() {
+        let a: Matrix<f64, 3, 3> = Matrix([[4.0, 7.0, 2.0], [3.0, 6.0, 1.0], [2.0, 5.0, 3.0]]);
+        let inv = a.inverse().unwrap();
+        let id = a * inv;
+        for r in 0..3 {
+            for c in 0..3 {
+                let expected = if r == c { 1.0 } else { 0.0 };
+                assert!((id[(r, c)] - expected).abs() < 1e-12);
+            }
+        }
+        assert_eq!(
+            Matrix([[0.0, 2.0], [4.0, 0.0]]).inverse(),
+            Some(Matrix([[0.0, 0.25], [0.5, 0.0]]))
+        );
+        assert_eq!(Matrix([[1.0, 2.0], [2.0, 4.0]]).inverse(), None);
+        assert_eq!(Square::<f32, 2>::zeros().inverse(), None);
+    }
+
+    #[test]
+    fn test_inverse_near_singular() {
+        // 消元后最后一个主元只剩舍入误差，不能当作可逆矩阵
+        let a: Square<f64, 3> = Matrix([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0], [7.0, 8.0, 9.0]]);
+        assert_eq!(a.inverse(), None);
+        assert_eq!((a * 0.1).inverse(), None);
+        assert_eq!(a.map(|x| x as f32).inverse(), None);
+
+        // 容差随矩阵缩放，量级很小但良态的矩阵仍然可逆
+        let tiny = Square::<f64, 3>::identity() * 1e-200;
+        let inv = tiny.inverse().unwrap();
+        assert!((inv[(1, 1)] - 1e200).abs() < 1e188);
+
+        // 容差按行缩放，各行量级相差很大也不会误判
+        let skewed: Square<f64, 2> = Matrix([[1e10, 0.0], [0.0, 1e-10]]);
+        assert_eq!(skewed.inverse(), Some(Matrix([[1e-10, 0.0], [0.0, 1e10]])));
+        let skewed: Square<f64, 2> = Matrix([[1e10, 2e10], [3e-10, 4e-10]]);
+        let inv = skewed.inverse().unwrap();
+        let expected = Matrix([[-2e-10, 1e10], [1.5e-10, -0.5e10]]);
+        for (a, b) in inv.0.iter().flatten().zip(expected.0.iter().flatten()) {
+            assert!((a - b).abs() <= b.abs() * 1e-12);
+        }
+    }
+}
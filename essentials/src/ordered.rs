@@ -0,0 +1,155 @@
+//! 全序浮点数
+//! f64 只实现了 PartialOrd：NaN 和任何值都不可比较，而 BTreeMap、BTreeSet、BinaryHeap 和 sort
+//! 都要求 Ord。OrderedF64 按 IEEE 754 的 totalOrder 谓词排序（即 f64::total_cmp）：
+//! -NaN < -inf < ... < -0.0 < +0.0 < ... < +inf < +NaN，
+//! 所以 -0.0 和 0.0 不相等，同一个比特模式的 NaN 和自己相等，Eq、Ord 和 Hash 三者一致。
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+#[derive(Clone, Copy, Default)]
+pub struct OrderedF64(pub f64);
+
+impl OrderedF64 {
+    pub fn into_inner(self) -> f64 {
+        self.0
+    }
+}
+
+impl From<f64> for OrderedF64 {
+    fn from(x: f64) -> Self {
+        OrderedF64(x)
+    }
+}
+
+impl From<OrderedF64> for f64 {
+    fn from(x: OrderedF64) -> Self {
+        x.0
+    }
+}
+
+impl PartialEq for OrderedF64 {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for OrderedF64 {}
+
+impl PartialOrd for OrderedF64 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OrderedF64 {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+/// totalOrder 下相等当且仅当比特模式相同，所以直接对比特模式求哈希
+impl Hash for OrderedF64 {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.to_bits().hash(state);
+    }
+}
+
+impl fmt::Debug for OrderedF64 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.0, f)
+    }
+}
+
+impl fmt::Display for OrderedF64 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OrderedF64;
+    use crate::algorithms::convex_hull;
+    use crate::smart_pointer::Point;
+    use std::cmp::Reverse;
+    use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashSet};
+
+    #[test]
+    fn test_total_order() {
+        let neg_nan = OrderedF64(-f64::NAN);
+        let expected = [
+            neg_nan,
+            OrderedF64(f64::NEG_INFINITY),
+            OrderedF64(-1.0),
+            OrderedF64(-0.0),
+            OrderedF64(0.0),
+            OrderedF64(f64::MIN_POSITIVE),
+            OrderedF64(1.0),
+            OrderedF64(f64::INFINITY),
+            OrderedF64(f64::NAN),
+        ];
+        let mut values = expected.to_vec();
+        values.reverse();
+        values.sort();
+        for (a, b) in values.iter().zip(expected.iter()) {
+            assert_eq!(a.0.to_bits(), b.0.to_bits());
+        }
+
+        assert_eq!(OrderedF64(f64::NAN), OrderedF64(f64::NAN));
+        assert_ne!(OrderedF64(-0.0), OrderedF64(0.0));
+        let set: HashSet<OrderedF64> = [0.0, -0.0, f64::NAN, f64::NAN, 1.5, 1.5]
+            .iter()
+            .map(|&x| OrderedF64(x))
+            .collect();
+        assert_eq!(set.len(), 4);
+        assert_eq!(
+            [3.0, f64::NAN, -2.0].iter().copied().map(OrderedF64).max(),
+            Some(OrderedF64(f64::NAN))
+        );
+    }
+
+    #[test]
+    fn test_point_in_ordered_collections() {
+        let p = Point::new;
+        let points = vec![
+            p(1.0, 2.0),
+            p(-1.0, 0.5),
+            p(1.0, -3.0),
+            p(0.0, 0.0),
+            p(1.0, 2.0),
+        ];
+
+        // Point 按 (x, y) 的字典序排列
+        let set: BTreeSet<Point> = points.iter().copied().collect();
+        assert_eq!(
+            set.into_iter().collect::<Vec<_>>(),
+            vec![p(-1.0, 0.5), p(0.0, 0.0), p(1.0, -3.0), p(1.0, 2.0)]
+        );
+
+        let mut visits = BTreeMap::new();
+        for &q in &points {
+            *visits.entry(q).or_insert(0) += 1;
+        }
+        assert_eq!(visits[&p(1.0, 2.0)], 2);
+
+        // 以到原点的距离为优先级的最小堆
+        let mut heap: BinaryHeap<_> = points
+            .iter()
+            .map(|&q| Reverse((OrderedF64(q.norm()), q)))
+            .collect();
+        assert_eq!(heap.pop().map(|Reverse((_, q))| q), Some(p(0.0, 0.0)));
+        assert_eq!(heap.pop().map(|Reverse((_, q))| q), Some(p(-1.0, 0.5)));
+
+        // 凸包结果可以直接排序去重
+        let mut hull = convex_hull(&points);
+        hull.extend(convex_hull(&points));
+        hull.sort();
+        hull.dedup();
+        assert_eq!(hull, vec![p(-1.0, 0.5), p(1.0, -3.0), p(1.0, 2.0)]);
+
+        // 全序下 -0.0 和 0.0 是不同的点，几何上的重合用 coincides 判断
+        assert_ne!(p(-0.0, 0.0), p(0.0, 0.0));
+        assert!(p(-0.0, 0.0).coincides(p(0.0, 0.0)));
+    }
+}
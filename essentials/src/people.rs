@@ -0,0 +1,137 @@
+//! 使用类型状态（type-state）的构建器
+//! PeopleBuilder<N, G> 的两个类型参数记录 name 和 gender 是否已经设置，
+//! 未设置时是零大小的 NoName / NoGender，设置后换成真正的值，
+//! build 只为 PeopleBuilder<String, Gender> 实现，缺少必填字段的构建在编译期就会报错：
+//! ```text
+//! People::builder().name("Alex").build();
+//! // no method named `build` found for struct `PeopleBuilder<String, NoGender>`
+//! ```
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Gender {
+    Male,
+    Female,
+}
+
+impl fmt::Display for Gender {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Gender::Male => write!(f, "male"),
+            Gender::Female => write!(f, "female"),
+        }
+    }
+}
+
+// Named-Field struct
+#[derive(Debug, Clone, PartialEq)]
+pub struct People {
+    name: String,
+    gender: Gender,
+}
+
+impl People {
+    pub fn new(name: impl Into<String>, gender: Gender) -> Self {
+        People {
+            name: name.into(),
+            gender,
+        }
+    }
+
+    pub fn builder() -> PeopleBuilder {
+        PeopleBuilder {
+            name: NoName,
+            gender: NoGender,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn gender(&self) -> Gender {
+        self.gender
+    }
+}
+
+/// 还没有设置 name
+#[derive(Debug, Clone, Copy)]
+pub struct NoName;
+
+/// 还没有设置 gender
+#[derive(Debug, Clone, Copy)]
+pub struct NoGender;
+
+#[derive(Debug, Clone)]
+pub struct PeopleBuilder<N = NoName, G = NoGender> {
+    name: N,
+    gender: G,
+}
+
+/// 每个 setter 都消耗旧的构建器并返回状态改变后的新类型，重复设置会覆盖之前的值
+impl<N, G> PeopleBuilder<N, G> {
+    pub fn name(self, name: impl Into<String>) -> PeopleBuilder<String, G> {
+        PeopleBuilder {
+            name: name.into(),
+            gender: self.gender,
+        }
+    }
+
+    pub fn gender(self, gender: Gender) -> PeopleBuilder<N, Gender> {
+        PeopleBuilder {
+            name: self.name,
+            gender,
+        }
+    }
+}
+
+impl PeopleBuilder<String, Gender> {
+    pub fn build(self) -> People {
+        People {
+            name: self.name,
+            gender: self.gender,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Gender, NoGender, NoName, People, PeopleBuilder};
+    use std::mem::size_of;
+
+    #[test]
+    fn test_builder() {
+        let alex = People::builder().name("Alex").gender(Gender::Male).build();
+        assert_eq!(alex, People::new("Alex", Gender::Male));
+        assert_eq!(alex.name(), "Alex");
+        assert_eq!(alex.gender().to_string(), "male");
+
+        // 设置顺序无关，名字可以来自运行时的 String
+        let input = String::from("  Beth\n");
+        let beth = People::builder()
+            .gender(Gender::Female)
+            .name(input.trim())
+            .build();
+        assert_eq!(beth.name(), "Beth");
+        assert_eq!(beth.gender(), Gender::Female);
+
+        let renamed = People::builder()
+            .name("x")
+            .name("Carl")
+            .gender(Gender::Male);
+        assert_eq!(renamed.build().name(), "Carl");
+    }
+
+    #[test]
+    fn test_state_types_are_zero_sized() {
+        assert_eq!(size_of::<PeopleBuilder<NoName, NoGender>>(), 0);
+        assert_eq!(
+            size_of::<PeopleBuilder<NoName, Gender>>(),
+            size_of::<Gender>()
+        );
+        assert_eq!(
+            size_of::<PeopleBuilder<String, NoGender>>(),
+            size_of::<String>()
+        );
+    }
+}
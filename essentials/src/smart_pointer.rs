@@ -1,3 +1,7 @@
+use crate::ordered::OrderedF64;
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+
 #[cfg(test)]
 mod tests {
     // Rust中的值默认被分配到栈内存，通过Box<T>将值boxing，在堆内存中分配
@@ -18,8 +22,42 @@ mod tests {
     }
 }
 
-#[derive(Debug, PartialEq)]
-struct Point {
-    x: f64,
-    y: f64,
+/// 比较、排序和哈希都基于 (OrderedF64(x), OrderedF64(y)) 的字典序，
+/// 所以 Point 可以放进 BTreeMap、BTreeSet 和 BinaryHeap
+#[derive(Debug, Clone, Copy)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Point {
+    fn key(&self) -> (OrderedF64, OrderedF64) {
+        (OrderedF64(self.x), OrderedF64(self.y))
+    }
+}
+
+impl PartialEq for Point {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for Point {}
+
+impl PartialOrd for Point {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Point {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+impl Hash for Point {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state);
+    }
 }
@@ -0,0 +1,28 @@
+//! 测试共用的工具
+use crate::smart_pointer::Point;
+
+/// xorshift 伪随机数，测试不依赖外部 crate
+pub struct Rng(pub u64);
+
+impl Rng {
+    pub fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    pub fn unit(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    pub fn cloud(&mut self, n: usize, grid: Option<u64>) -> Vec<Point> {
+        (0..n)
+            .map(|_| match grid {
+                // 小网格上会产生大量重复点和共线点
+                Some(g) => Point::new((self.next() % g) as f64, (self.next() % g) as f64),
+                None => Point::new(self.unit() * 1000.0 - 500.0, self.unit() * 1000.0 - 500.0),
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,728 @@
+//! 任意精度整数
+//! 数值用符号加绝对值表示，绝对值按 2^32 进制小端存放在 Vec<u32> 中，最高位的 limb 永远不为零，
+//! 零的绝对值是空 Vec 且符号为正，这样同一个数只有一种表示，可以直接派生 PartialEq 和 Hash。
+use crate::traits::{Add2, Div2, Mul2, Sub2};
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt;
+use std::ops::{Add, Div, Mul, Neg, Rem, Sub};
+use std::str::FromStr;
+
+/// 两个操作数都超过这个 limb 数时改用 Karatsuba 乘法
+const KARATSUBA_THRESHOLD: usize = 32;
+
+#[derive(Clone, PartialEq, Eq, Hash, Default)]
+pub struct BigInt {
+    negative: bool,
+    mag: Vec<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseBigIntError {
+    Empty,
+    InvalidDigit,
+}
+
+impl fmt::Display for ParseBigIntError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseBigIntError::Empty => write!(f, "cannot parse integer from empty string"),
+            ParseBigIntError::InvalidDigit => write!(f, "invalid digit found in string"),
+        }
+    }
+}
+
+impl Error for ParseBigIntError {}
+
+fn trim(mut mag: Vec<u32>) -> Vec<u32> {
+    while mag.last() == Some(&0) {
+        mag.pop();
+    }
+    mag
+}
+
+fn cmp_mag(a: &[u32], b: &[u32]) -> Ordering {
+    a.len()
+        .cmp(&b.len())
+        .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let (long, short) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    let mut out = Vec::with_capacity(long.len() + 1);
+    let mut carry = 0u64;
+    for (i, &x) in long.iter().enumerate() {
+        let sum = x as u64 + short.get(i).copied().unwrap_or(0) as u64 + carry;
+        out.push(sum as u32);
+        carry = sum >> 32;
+    }
+    if carry > 0 {
+        out.push(carry as u32);
+    }
+    out
+}
+
+/// 要求 a >= b
+fn sub_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut out = Vec::with_capacity(a.len());
+    let mut borrow = 0i64;
+    for (i, &x) in a.iter().enumerate() {
+        let diff = x as i64 - b.get(i).copied().unwrap_or(0) as i64 - borrow;
+        out.push(diff as u32);
+        borrow = (diff < 0) as i64;
+    }
+    debug_assert_eq!(borrow, 0);
+    trim(out)
+}
+
+/// 把 x * 2^(32 * shift) 累加到 acc 上
+fn add_shifted(acc: &mut Vec<u32>, x: &[u32], shift: usize) {
+    if acc.len() < x.len() + shift {
+        acc.resize(x.len() + shift, 0);
+    }
+    let mut carry = 0u64;
+    let mut i = shift;
+    for &limb in x {
+        let sum = acc[i] as u64 + limb as u64 + carry;
+        acc[i] = sum as u32;
+        carry = sum >> 32;
+        i += 1;
+    }
+    while carry > 0 {
+        if i == acc.len() {
+            acc.push(0);
+        }
+        let sum = acc[i] as u64 + carry;
+        acc[i] = sum as u32;
+        carry = sum >> 32;
+        i += 1;
+    }
+}
+
+fn mul_schoolbook(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut out = vec![0u32; a.len() + b.len()];
+    for (i, &x) in a.iter().enumerate() {
+        let mut carry = 0u64;
+        for (j, &y) in b.iter().enumerate() {
+            let t = x as u64 * y as u64 + out[i + j] as u64 + carry;
+            out[i + j] = t as u32;
+            carry = t >> 32;
+        }
+        out[i + b.len()] = carry as u32;
+    }
+    trim(out)
+}
+
+/// Karatsuba：a = a1 * B^h + a0，b = b1 * B^h + b0，
+/// a * b = z2 * B^2h + z1 * B^h + z0，其中 z1 = (a0 + a1)(b0 + b1) - z2 - z0，
+/// 四次乘法变成三次，复杂度从 O(n^2) 降到 O(n^1.585)
+fn mul_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+    if a.len().min(b.len()) < KARATSUBA_THRESHOLD {
+        return mul_schoolbook(a, b);
+    }
+    let h = a.len().min(b.len()) / 2;
+    let (a0, a1) = (trim(a[..h].to_vec()), &a[h..]);
+    let (b0, b1) = (trim(b[..h].to_vec()), &b[h..]);
+    let z0 = mul_mag(&a0, &b0);
+    let z2 = mul_mag(a1, b1);
+    let z1 = mul_mag(&add_mag(&a0, a1), &add_mag(&b0, b1));
+    let z1 = sub_mag(&sub_mag(&z1, &z2), &z0);
+
+    let mut out = z0;
+    add_shifted(&mut out, &z1, h);
+    add_shifted(&mut out, &z2, 2 * h);
+    trim(out)
+}
+
+/// 原地计算 mag * m + a
+fn mul_small_add(mag: &mut Vec<u32>, m: u32, a: u32) {
+    let mut carry = a as u64;
+    for limb in mag.iter_mut() {
+        let t = *limb as u64 * m as u64 + carry;
+        *limb = t as u32;
+        carry = t >> 32;
+    }
+    if carry > 0 {
+        mag.push(carry as u32);
+    }
+}
+
+/// 原地除以 d，返回余数
+fn div_small(mag: &mut Vec<u32>, d: u32) -> u32 {
+    let mut rem = 0u64;
+    for limb in mag.iter_mut().rev() {
+        let cur = (rem << 32) | *limb as u64;
+        *limb = (cur / d as u64) as u32;
+        rem = cur % d as u64;
+    }
+    while mag.last() == Some(&0) {
+        mag.pop();
+    }
+    rem as u32
+}
+
+/// Knuth 算法 D（TAOCP 4.3.1），返回商和余数
+fn divrem_mag(u: &[u32], v: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    assert!(!v.is_empty(), "attempt to divide by zero");
+    if cmp_mag(u, v) == Ordering::Less {
+        return (Vec::new(), u.to_vec());
+    }
+    if v.len() == 1 {
+        let mut q = u.to_vec();
+        let r = div_small(&mut q, v[0]);
+        return (q, trim(vec![r]));
+    }
+
+    // 左移使除数最高位为 1，这样估计的商最多比真实值大 2
+    let s = v[v.len() - 1].leading_zeros();
+    let shl = |x: &[u32], extra: usize| {
+        let mut out = vec![0u32; x.len() + extra];
+        for (i, &limb) in x.iter().enumerate() {
+            out[i] |= limb << s;
+            if s > 0 && i + 1 < out.len() {
+                out[i + 1] |= limb >> (32 - s);
+            }
+        }
+        out
+    };
+    let vn = shl(v, 0);
+    let mut un = shl(u, 1);
+    let (n, m) = (v.len(), u.len() - v.len());
+    let b = 1u64 << 32;
+    let mut q = vec![0u32; m + 1];
+
+    for j in (0..=m).rev() {
+        let num = ((un[j + n] as u64) << 32) | un[j + n - 1] as u64;
+        let mut qhat = num / vn[n - 1] as u64;
+        let mut rhat = num % vn[n - 1] as u64;
+        while qhat >= b || qhat * vn[n - 2] as u64 > ((rhat << 32) | un[j + n - 2] as u64) {
+            qhat -= 1;
+            rhat += vn[n - 1] as u64;
+            if rhat >= b {
+                break;
+            }
+        }
+
+        // un[j..=j+n] -= qhat * vn
+        let mut borrow = 0i64;
+        for i in 0..n {
+            let p = qhat * vn[i] as u64;
+            let t = un[i + j] as i64 - borrow - (p & 0xffff_ffff) as i64;
+            un[i + j] = t as u32;
+            borrow = (p >> 32) as i64 - (t >> 32);
+        }
+        let t = un[j + n] as i64 - borrow;
+        un[j + n] = t as u32;
+
+        q[j] = qhat as u32;
+        // 估计的商大了 1，加回一个除数
+        if t < 0 {
+            q[j] -= 1;
+            let mut carry = 0u64;
+            for i in 0..n {
+                let t = un[i + j] as u64 + vn[i] as u64 + carry;
+                un[i + j] = t as u32;
+                carry = t >> 32;
+            }
+            un[j + n] = un[j + n].wrapping_add(carry as u32);
+        }
+    }
+
+    let r = (0..n)
+        .map(|i| {
+            if s == 0 {
+                un[i]
+            } else {
+                (un[i] >> s) | (un[i + 1] << (32 - s))
+            }
+        })
+        .collect();
+    (trim(q), trim(r))
+}
+
+/// radix^k 不超过 u32::MAX 的最大 k，以及 radix^k 本身，用于按块转换进制
+fn radix_chunk(radix: u32) -> (usize, u32) {
+    let mut k = 1;
+    let mut power = radix;
+    while let Some(next) = power.checked_mul(radix) {
+        power = next;
+        k += 1;
+    }
+    (k, power)
+}
+
+impl BigInt {
+    fn from_parts(negative: bool, mag: Vec<u32>) -> Self {
+        let mag = trim(mag);
+        BigInt {
+            negative: negative && !mag.is_empty(),
+            mag,
+        }
+    }
+
+    pub fn zero() -> Self {
+        BigInt::default()
+    }
+
+    pub fn one() -> Self {
+        BigInt::from(1)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.mag.is_empty()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    pub fn abs(&self) -> Self {
+        BigInt::from_parts(false, self.mag.clone())
+    }
+
+    /// 绝对值的二进制位数，零为 0
+    pub fn bits(&self) -> u64 {
+        match self.mag.last() {
+            Some(top) => self.mag.len() as u64 * 32 - top.leading_zeros() as u64,
+            None => 0,
+        }
+    }
+
+    /// 和原生整数一样向零截断：商的符号为两者符号的异或，余数的符号与被除数相同
+    /// 除数为零时 panic
+    pub fn div_rem(&self, other: &BigInt) -> (BigInt, BigInt) {
+        let (q, r) = divrem_mag(&self.mag, &other.mag);
+        (
+            BigInt::from_parts(self.negative != other.negative, q),
+            BigInt::from_parts(self.negative, r),
+        )
+    }
+
+    /// 平方求幂
+    pub fn pow(&self, mut exp: u32) -> Self {
+        let mut base = self.clone();
+        let mut acc = BigInt::one();
+        while exp > 0 {
+            if exp & 1 == 1 {
+                acc = &acc * &base;
+            }
+            exp >>= 1;
+            if exp > 0 {
+                base = &base * &base;
+            }
+        }
+        acc
+    }
+
+    /// 可选的 + 或 - 号后跟至少一位数字，数字不区分大小写
+    /// 和 i32::from_str_radix 一样，radix 不在 2..=36 时 panic
+    pub fn from_str_radix(s: &str, radix: u32) -> Result<Self, ParseBigIntError> {
+        assert!(
+            (2..=36).contains(&radix),
+            "radix must be in the range 2..=36, got {}",
+            radix
+        );
+        let (negative, digits) = match s.as_bytes().first() {
+            Some(b'-') => (true, &s[1..]),
+            Some(b'+') => (false, &s[1..]),
+            _ => (false, s),
+        };
+        if digits.is_empty() {
+            return Err(ParseBigIntError::Empty);
+        }
+
+        let (k, power) = radix_chunk(radix);
+        let mut mag = Vec::new();
+        let (mut chunk, mut len) = (0u32, 0);
+        for c in digits.chars() {
+            let d = c.to_digit(radix).ok_or(ParseBigIntError::InvalidDigit)?;
+            chunk = chunk * radix + d;
+            len += 1;
+            if len == k {
+                mul_small_add(&mut mag, power, chunk);
+                chunk = 0;
+                len = 0;
+            }
+        }
+        if len > 0 {
+            mul_small_add(&mut mag, radix.pow(len as u32), chunk);
+        }
+        Ok(BigInt::from_parts(negative, mag))
+    }
+
+    /// 小写字母表示 10 以上的数字，负数带 - 号
+    pub fn to_str_radix(&self, radix: u32) -> String {
+        assert!(
+            (2..=36).contains(&radix),
+            "radix must be in the range 2..=36, got {}",
+            radix
+        );
+        let mut s = self.digits_radix(radix);
+        if self.negative {
+            s.insert(0, '-');
+        }
+        s
+    }
+
+    /// 绝对值的各位数字，每次除以 radix^k 取出 k 位
+    fn digits_radix(&self, radix: u32) -> String {
+        if self.is_zero() {
+            return "0".to_string();
+        }
+        let (k, power) = radix_chunk(radix);
+        let mut mag = self.mag.clone();
+        let mut rev = Vec::new();
+        while !mag.is_empty() {
+            let mut chunk = div_small(&mut mag, power);
+            for _ in 0..k {
+                rev.push(std::char::from_digit(chunk % radix, radix).unwrap());
+                chunk /= radix;
+                if mag.is_empty() && chunk == 0 {
+                    break;
+                }
+            }
+        }
+        rev.iter().rev().collect()
+    }
+}
+
+impl FromStr for BigInt {
+    type Err = ParseBigIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        BigInt::from_str_radix(s, 10)
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad_integral(!self.negative, "", &self.digits_radix(10))
+    }
+}
+
+impl fmt::Debug for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl fmt::LowerHex for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad_integral(!self.negative, "0x", &self.digits_radix(16))
+    }
+}
+
+impl fmt::Binary for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad_integral(!self.negative, "0b", &self.digits_radix(2))
+    }
+}
+
+macro_rules! impl_from_unsigned {
+    ($($t:ty)*) => {
+        $(impl From<$t> for BigInt {
+            fn from(n: $t) -> Self {
+                let mut n = n as u128;
+                let mut mag = Vec::new();
+                while n > 0 {
+                    mag.push(n as u32);
+                    n >>= 32;
+                }
+                BigInt::from_parts(false, mag)
+            }
+        })*
+    };
+}
+
+macro_rules! impl_from_signed {
+    ($($t:ty)*) => {
+        $(impl From<$t> for BigInt {
+            fn from(n: $t) -> Self {
+                let abs = BigInt::from((n as i128).unsigned_abs());
+                BigInt::from_parts(n < 0, abs.mag)
+            }
+        })*
+    };
+}
+
+impl_from_unsigned!(u8 u16 u32 u64 u128 usize);
+impl_from_signed!(i8 i16 i32 i64 i128 isize);
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => cmp_mag(&self.mag, &other.mag),
+            (true, true) => cmp_mag(&other.mag, &self.mag),
+        }
+    }
+}
+
+impl Neg for &BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> BigInt {
+        BigInt::from_parts(!self.negative, self.mag.clone())
+    }
+}
+
+impl Neg for BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> BigInt {
+        BigInt::from_parts(!self.negative, self.mag)
+    }
+}
+
+impl Add<&BigInt> for &BigInt {
+    type Output = BigInt;
+
+    fn add(self, rhs: &BigInt) -> BigInt {
+        if self.negative == rhs.negative {
+            return BigInt::from_parts(self.negative, add_mag(&self.mag, &rhs.mag));
+        }
+        // 异号相加：大的绝对值减去小的绝对值，符号跟随绝对值大的一方
+        match cmp_mag(&self.mag, &rhs.mag) {
+            Ordering::Less => BigInt::from_parts(rhs.negative, sub_mag(&rhs.mag, &self.mag)),
+            _ => BigInt::from_parts(self.negative, sub_mag(&self.mag, &rhs.mag)),
+        }
+    }
+}
+
+impl Sub<&BigInt> for &BigInt {
+    type Output = BigInt;
+
+    fn sub(self, rhs: &BigInt) -> BigInt {
+        if rhs.is_zero() {
+            return self.clone();
+        }
+        let neg_rhs = BigInt {
+            negative: !rhs.negative,
+            mag: rhs.mag.clone(),
+        };
+        self + &neg_rhs
+    }
+}
+
+impl Mul<&BigInt> for &BigInt {
+    type Output = BigInt;
+
+    fn mul(self, rhs: &BigInt) -> BigInt {
+        BigInt::from_parts(self.negative != rhs.negative, mul_mag(&self.mag, &rhs.mag))
+    }
+}
+
+impl Div<&BigInt> for &BigInt {
+    type Output = BigInt;
+
+    fn div(self, rhs: &BigInt) -> BigInt {
+        self.div_rem(rhs).0
+    }
+}
+
+impl Rem<&BigInt> for &BigInt {
+    type Output = BigInt;
+
+    fn rem(self, rhs: &BigInt) -> BigInt {
+        self.div_rem(rhs).1
+    }
+}
+
+/// 引用版本是真正的实现，按值的版本都转发过去，这样 sum<T: Add<Output = T>> 可以直接使用 BigInt
+macro_rules! forward_binop {
+    ($($imp:ident $method:ident),*) => {
+        $(
+            impl $imp<BigInt> for BigInt {
+                type Output = BigInt;
+
+                fn $method(self, rhs: BigInt) -> BigInt {
+                    (&self).$method(&rhs)
+                }
+            }
+
+            impl<'a> $imp<&'a BigInt> for BigInt {
+                type Output = BigInt;
+
+                fn $method(self, rhs: &'a BigInt) -> BigInt {
+                    (&self).$method(rhs)
+                }
+            }
+
+            impl<'a> $imp<BigInt> for &'a BigInt {
+                type Output = BigInt;
+
+                fn $method(self, rhs: BigInt) -> BigInt {
+                    self.$method(&rhs)
+                }
+            }
+        )*
+    };
+}
+
+forward_binop!(Add add, Sub sub, Mul mul, Div div, Rem rem);
+
+/// 任意精度整数不会溢出，Add2 一族的结果类型就是 BigInt 本身
+macro_rules! impl_tower {
+    ($($imp:ident $method:ident),*) => {
+        $(impl $imp for BigInt {
+            type Output = BigInt;
+
+            fn $method(self, rhs: BigInt) -> BigInt {
+                (&self).$method(&rhs)
+            }
+        })*
+    };
+}
+
+impl_tower!(Add2 add, Sub2 sub, Mul2 mul, Div2 div);
+
+impl std::iter::Sum for BigInt {
+    fn sum<I: Iterator<Item = BigInt>>(iter: I) -> Self {
+        iter.fold(BigInt::zero(), |acc, x| acc + x)
+    }
+}
+
+impl std::iter::Product for BigInt {
+    fn product<I: Iterator<Item = BigInt>>(iter: I) -> Self {
+        iter.fold(BigInt::one(), |acc, x| acc * x)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{mul_mag, mul_schoolbook, BigInt, ParseBigIntError, KARATSUBA_THRESHOLD};
+    use crate::test_util::Rng;
+
+    /// 随机符号、limbs 个随机 limb 的大整数
+    fn random(rng: &mut Rng, limbs: usize) -> BigInt {
+        let mag = (0..limbs).map(|_| rng.next() as u32).collect();
+        BigInt::from_parts(rng.next() & 1 == 0, mag)
+    }
+
+    fn big(s: &str) -> BigInt {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_matches_i128() {
+        let values = [
+            0i128,
+            1,
+            -1,
+            7,
+            -7,
+            u32::MAX as i128,
+            -(u32::MAX as i128) - 1,
+            u64::MAX as i128,
+            i64::MIN as i128,
+            123_456_789_012_345_678_901,
+            -98_765_432_109_876_543,
+        ];
+        for &a in &values {
+            for &b in &values {
+                let (x, y) = (BigInt::from(a), BigInt::from(b));
+                assert_eq!(&x + &y, BigInt::from(a + b));
+                assert_eq!(&x - &y, BigInt::from(a - b));
+                assert_eq!(x.cmp(&y), a.cmp(&b));
+                if let Some(p) = a.checked_mul(b) {
+                    assert_eq!(&x * &y, BigInt::from(p));
+                }
+                if b != 0 {
+                    assert_eq!(x.div_rem(&y), (BigInt::from(a / b), BigInt::from(a % b)));
+                }
+            }
+            assert_eq!(BigInt::from(a).to_string(), a.to_string());
+        }
+    }
+
+    #[test]
+    fn test_large_arithmetic() {
+        let two = BigInt::from(2);
+        assert_eq!(
+            two.pow(128).to_string(),
+            "340282366920938463463374607431768211456"
+        );
+        let factorial: BigInt = (1..=30).map(BigInt::from).product();
+        assert_eq!(factorial, big("265252859812191058636308480000000"));
+        assert_eq!(
+            &factorial / &big("265252859812191058636308480000000"),
+            BigInt::one()
+        );
+        assert_eq!(
+            big("-1000000000000000000000000000000") % big("7"),
+            big("-1")
+        );
+        assert_eq!(BigInt::from(-3).pow(3), BigInt::from(-27));
+        assert_eq!(BigInt::zero().pow(0), BigInt::one());
+        assert!(!(-BigInt::zero()).is_negative());
+        assert_eq!(two.pow(100).bits(), 101);
+    }
+
+    #[test]
+    fn test_karatsuba_and_division_random() {
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+        for round in 0..40 {
+            let (la, lb) = (1 + round * 5, 1 + (round * 3) % 97);
+            let (a, b) = (random(&mut rng, la), random(&mut rng, lb));
+            if la >= KARATSUBA_THRESHOLD && lb >= KARATSUBA_THRESHOLD {
+                assert_eq!(mul_mag(&a.mag, &b.mag), mul_schoolbook(&a.mag, &b.mag));
+            }
+            let p = &a * &b;
+            if !b.is_zero() {
+                assert_eq!(&p / &b, a);
+                assert!((&p % &b).is_zero());
+                let (q, r) = a.div_rem(&b);
+                assert_eq!(&(&q * &b) + &r, a);
+                assert!(r.abs() < b.abs());
+                assert!(r.is_zero() || r.is_negative() == a.is_negative());
+            }
+            assert_eq!(&(&a + &b) - &b, a);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "divide by zero")]
+    fn test_divide_by_zero() {
+        let _ = BigInt::one() / BigInt::zero();
+    }
+
+    #[test]
+    fn test_radix() {
+        let mut rng = Rng(99);
+        for radix in 2..=36 {
+            for limbs in 0..6 {
+                let n = random(&mut rng, limbs);
+                let s = n.to_str_radix(radix);
+                assert_eq!(BigInt::from_str_radix(&s, radix), Ok(n.clone()));
+                assert_eq!(BigInt::from_str_radix(&s.to_uppercase(), radix), Ok(n));
+            }
+            let x = 0x7fff_ffff_ffffi64;
+            assert_eq!(
+                BigInt::from_str_radix(&format!("-{:x}", x), 16),
+                Ok(BigInt::from(-x))
+            );
+        }
+        assert_eq!(BigInt::from(255).to_str_radix(2), "11111111");
+        assert_eq!(BigInt::from(-35).to_str_radix(36), "-z");
+        assert_eq!(format!("{:#x}", BigInt::from(255)), "0xff");
+        assert_eq!(format!("{:b}", BigInt::from(-5)), "-101");
+        assert_eq!(format!("{:>6}", BigInt::from(-42)), "   -42");
+        assert_eq!(format!("{:+}", BigInt::from(42)), "+42");
+        assert_eq!(big("+00012"), BigInt::from(12));
+        assert_eq!(big("-0"), BigInt::zero());
+        assert_eq!("".parse::<BigInt>(), Err(ParseBigIntError::Empty));
+        assert_eq!("-".parse::<BigInt>(), Err(ParseBigIntError::Empty));
+        assert_eq!("12a".parse::<BigInt>(), Err(ParseBigIntError::InvalidDigit));
+        assert_eq!(
+            BigInt::from_str_radix("12", 2),
+            Err(ParseBigIntError::InvalidDigit)
+        );
+    }
+}
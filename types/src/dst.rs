@@ -0,0 +1,368 @@
+//! 自定义动态大小类型（DST）
+//! 最后一个字段是 [T] 的结构体本身也是 DST：HeaderSlice<H, T> 的大小取决于切片长度，
+//! 只能放在指针后面使用，&HeaderSlice、Box<HeaderSlice> 都是胖指针，元数据就是切片长度。
+//! 一次分配就能同时放下头部和元素，不需要 (H, Vec<T>) 那样的第二次分配。
+//!
+//! 标准库没有提供直接构造这类值的方法，所以 new_boxed 手动按 repr(C) 的规则计算内存布局、
+//! 逐个写入头部和元素，再把 *mut [T] 转换成 *mut HeaderSlice<H, T>：
+//! 指针转换会保留元数据（长度），得到的胖指针交给 Box 管理。
+//!
+//! ThinHeaderSlice 则把长度也存进分配的内存里，指针本身只有一个 usize 大，
+//! 需要访问切片时再从内存中读出长度，拼回胖指针。
+use std::alloc::{self, Layout};
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::ptr::{self, NonNull};
+use std::rc::Rc;
+
+#[repr(C)]
+pub struct HeaderSlice<H, T> {
+    pub header: H,
+    pub slice: [T],
+}
+
+/// 构造过程中途 panic 时（元素的迭代器可能 panic），释放已经写入的头部、元素和内存
+struct Guard<H, T> {
+    base: *mut u8,
+    layout: Layout,
+    elems: *mut T,
+    written: usize,
+    header: PhantomData<H>,
+}
+
+impl<H, T> Drop for Guard<H, T> {
+    fn drop(&mut self) {
+        unsafe {
+            ptr::drop_in_place(self.base as *mut H);
+            ptr::drop_in_place(ptr::slice_from_raw_parts_mut(self.elems, self.written));
+            if self.layout.size() != 0 {
+                alloc::dealloc(self.base, self.layout);
+            }
+        }
+    }
+}
+
+impl<H, T> HeaderSlice<H, T> {
+    /// 头部和 len 个元素的内存布局，以及元素相对于起始地址的偏移，与 repr(C) 的规则一致
+    fn layout(len: usize) -> (Layout, usize) {
+        let array = Layout::array::<T>(len).expect("HeaderSlice is too large");
+        let (layout, offset) = Layout::new::<H>()
+            .extend(array)
+            .expect("HeaderSlice is too large");
+        (layout.pad_to_align(), offset)
+    }
+
+    /// 元素个数取自 ExactSizeIterator::len，迭代器实际产生的元素个数不符时 panic
+    pub fn new_boxed<I>(header: H, items: I) -> Box<Self>
+    where
+        I: IntoIterator<Item = T>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        let mut items = items.into_iter();
+        let len = items.len();
+        let (layout, offset) = Self::layout(len);
+        unsafe {
+            // 零大小的布局不能交给分配器，用一个对齐的悬垂指针代替，Box 同样不会释放它
+            let base = if layout.size() == 0 {
+                ptr::without_provenance_mut(layout.align())
+            } else {
+                let p = alloc::alloc(layout);
+                if p.is_null() {
+                    alloc::handle_alloc_error(layout);
+                }
+                p
+            };
+            ptr::write(base as *mut H, header);
+            let mut guard = Guard::<H, T> {
+                base,
+                layout,
+                elems: base.add(offset) as *mut T,
+                written: 0,
+                header: PhantomData,
+            };
+            while guard.written < len {
+                let item = items
+                    .next()
+                    .expect("iterator yielded fewer items than its len()");
+                ptr::write(guard.elems.add(guard.written), item);
+                guard.written += 1;
+            }
+            assert!(
+                items.next().is_none(),
+                "iterator yielded more items than its len()"
+            );
+            std::mem::forget(guard);
+            let fat = ptr::slice_from_raw_parts_mut(base as *mut T, len) as *mut Self;
+            Box::from_raw(fat)
+        }
+    }
+
+    /// Rc<T> 可以从 Box<T> 转换而来（T 可以是 DST），内容会被移动到带引用计数的新分配中
+    pub fn new_rc<I>(header: H, items: I) -> Rc<Self>
+    where
+        I: IntoIterator<Item = T>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        Rc::from(Self::new_boxed(header, items))
+    }
+
+    pub fn len(&self) -> usize {
+        self.slice.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slice.is_empty()
+    }
+}
+
+impl<H: Clone, T: Clone> Clone for Box<HeaderSlice<H, T>> {
+    fn clone(&self) -> Self {
+        HeaderSlice::new_boxed(self.header.clone(), self.slice.iter().cloned())
+    }
+}
+
+impl<H: fmt::Debug, T: fmt::Debug> fmt::Debug for HeaderSlice<H, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("HeaderSlice")
+            .field("header", &self.header)
+            .field("slice", &&self.slice)
+            .finish()
+    }
+}
+
+/// 长度和头部存放在一起，放在元素之前
+#[repr(C)]
+struct Counted<H> {
+    len: usize,
+    header: H,
+}
+
+/// 瘦指针版本：和 Box<HeaderSlice<H, T>> 拥有同样的数据，但指针只有一个 usize 大
+pub struct ThinHeaderSlice<H, T> {
+    ptr: NonNull<Counted<H>>,
+    marker: PhantomData<Box<HeaderSlice<Counted<H>, T>>>,
+}
+
+// 和 Box 一样，拥有 H 和 T 的所有权
+unsafe impl<H: Send, T: Send> Send for ThinHeaderSlice<H, T> {}
+unsafe impl<H: Sync, T: Sync> Sync for ThinHeaderSlice<H, T> {}
+
+impl<H, T> ThinHeaderSlice<H, T> {
+    pub fn new<I>(header: H, items: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        let items = items.into_iter();
+        let counted = Counted {
+            len: items.len(),
+            header,
+        };
+        let fat = Box::into_raw(HeaderSlice::new_boxed(counted, items));
+        ThinHeaderSlice {
+            // 丢弃元数据，只保留地址
+            ptr: unsafe { NonNull::new_unchecked(fat as *mut Counted<H>) },
+            marker: PhantomData,
+        }
+    }
+
+    /// 从分配的内存中读出长度，重新拼出胖指针
+    fn fat(&self) -> *mut HeaderSlice<Counted<H>, T> {
+        unsafe {
+            let len = (*self.ptr.as_ptr()).len;
+            ptr::slice_from_raw_parts_mut(self.ptr.as_ptr() as *mut T, len) as *mut _
+        }
+    }
+
+    pub fn header(&self) -> &H {
+        unsafe { &(*self.ptr.as_ptr()).header }
+    }
+
+    pub fn header_mut(&mut self) -> &mut H {
+        unsafe { &mut (*self.ptr.as_ptr()).header }
+    }
+}
+
+impl<H, T> Deref for ThinHeaderSlice<H, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        unsafe { &(*self.fat()).slice }
+    }
+}
+
+impl<H, T> DerefMut for ThinHeaderSlice<H, T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        unsafe { &mut (*self.fat()).slice }
+    }
+}
+
+impl<H, T> Drop for ThinHeaderSlice<H, T> {
+    fn drop(&mut self) {
+        unsafe { drop(Box::from_raw(self.fat())) }
+    }
+}
+
+impl<H: Clone, T: Clone> Clone for ThinHeaderSlice<H, T> {
+    fn clone(&self) -> Self {
+        ThinHeaderSlice::new(self.header().clone(), self.iter().cloned())
+    }
+}
+
+impl<H: fmt::Debug, T: fmt::Debug> fmt::Debug for ThinHeaderSlice<H, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ThinHeaderSlice")
+            .field("header", self.header())
+            .field("slice", &&**self)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HeaderSlice, ThinHeaderSlice};
+    use std::cell::Cell;
+    use std::mem::{size_of, size_of_val};
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    use std::rc::Rc;
+
+    /// drop 时计数加一
+    struct Noisy<'a>(&'a Cell<usize>, u32);
+
+    impl Drop for Noisy<'_> {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    #[test]
+    fn test_boxed() {
+        let mut b = HeaderSlice::new_boxed("primes", vec![2u32, 3, 5, 7]);
+        assert_eq!(b.header, "primes");
+        assert_eq!(b.len(), 4);
+        assert_eq!(b.slice[2], 5);
+        b.slice[0] = 1;
+        assert_eq!(&b.slice, &[1, 3, 5, 7]);
+        assert_eq!(format!("{:?}", b.clone()), format!("{:?}", b));
+
+        // 胖指针，元数据是切片长度；大小是头部加上元素，并按对齐补齐
+        assert_eq!(
+            size_of::<Box<HeaderSlice<u8, u32>>>(),
+            2 * size_of::<usize>()
+        );
+        let small = HeaderSlice::new_boxed(1u8, vec![1u32, 2, 3]);
+        assert_eq!(size_of_val(&*small), 4 + 3 * 4);
+        let empty = HeaderSlice::<u64, u8>::new_boxed(9, Vec::new());
+        assert!(empty.is_empty());
+        assert_eq!(size_of_val(&*empty), 8);
+
+        // 零大小的头部和元素
+        let zst = HeaderSlice::new_boxed((), vec![(); 3]);
+        assert_eq!(zst.len(), 3);
+        assert_eq!(size_of_val(&*zst), 0);
+    }
+
+    #[test]
+    fn test_rc() {
+        let rc = HeaderSlice::new_rc(String::from("names"), vec!["a", "b"]);
+        let other = Rc::clone(&rc);
+        assert_eq!(other.header, "names");
+        assert_eq!(other.slice[1], "b");
+        assert_eq!(Rc::strong_count(&rc), 2);
+    }
+
+    #[test]
+    fn test_thin() {
+        let mut t = ThinHeaderSlice::new(String::from("odd"), (0..5).map(|i| i * 2 + 1));
+        assert_eq!(
+            size_of::<ThinHeaderSlice<String, i32>>(),
+            size_of::<usize>()
+        );
+        assert_eq!(
+            size_of::<Option<ThinHeaderSlice<String, i32>>>(),
+            size_of::<usize>()
+        );
+        assert_eq!(t.header(), "odd");
+        assert_eq!(t.len(), 5);
+        assert_eq!(t[4], 9);
+        t[0] = -1;
+        t.header_mut().push('!');
+        assert_eq!(&*t, &[-1, 3, 5, 7, 9]);
+        assert_eq!(t.clone().header(), "odd!");
+        assert_eq!(
+            format!("{:?}", t),
+            r#"ThinHeaderSlice { header: "odd!", slice: [-1, 3, 5, 7, 9] }"#
+        );
+
+        let empty = ThinHeaderSlice::<(), u8>::new((), Vec::new());
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_drop() {
+        let drops = Cell::new(0);
+        let items = (0..4).map(|i| Noisy(&drops, i)).collect::<Vec<_>>();
+        let b = HeaderSlice::new_boxed(Noisy(&drops, 100), items);
+        assert_eq!(b.slice[3].1, 3);
+        assert_eq!(drops.get(), 0);
+        drop(b);
+        assert_eq!(drops.get(), 5);
+
+        drops.set(0);
+        let rc = HeaderSlice::new_rc(Noisy(&drops, 0), vec![Noisy(&drops, 1)]);
+        let rc2 = Rc::clone(&rc);
+        drop(rc);
+        assert_eq!(drops.get(), 0);
+        drop(rc2);
+        assert_eq!(drops.get(), 2);
+
+        drops.set(0);
+        let t = ThinHeaderSlice::new(Noisy(&drops, 0), (1..4).map(|i| Noisy(&drops, i)));
+        assert_eq!(t[1].1, 2);
+        drop(t);
+        assert_eq!(drops.get(), 4);
+    }
+
+    #[test]
+    fn test_panic_during_construction() {
+        let drops = Cell::new(0);
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            let items = (0..5).map(|i| {
+                if i == 3 {
+                    panic!("boom");
+                }
+                Noisy(&drops, i)
+            });
+            HeaderSlice::new_boxed(Noisy(&drops, 100), items)
+        }));
+        assert!(result.is_err());
+        // 头部和已经写入的 3 个元素都被释放
+        assert_eq!(drops.get(), 4);
+
+        // 迭代器报告的长度和实际不符
+        let short = catch_unwind(|| {
+            let mut v = vec![1, 2, 3].into_iter();
+            let lying = std::iter::from_fn(move || v.next()).take(3);
+            HeaderSlice::new_boxed((), ExactLen(lying, 4))
+        });
+        assert!(short.is_err());
+    }
+
+    /// 故意报告错误长度的迭代器
+    struct ExactLen<I>(I, usize);
+
+    impl<I: Iterator> Iterator for ExactLen<I> {
+        type Item = I::Item;
+
+        fn next(&mut self) -> Option<I::Item> {
+            self.0.next()
+        }
+
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            (self.1, Some(self.1))
+        }
+    }
+
+    impl<I: Iterator> ExactSizeIterator for ExactLen<I> {}
+}
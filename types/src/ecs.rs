@@ -0,0 +1,513 @@
+//! 以类型为键的组件存储（一个迷你 ECS）
+//! 实体只是一个带代数（generation）的编号，组件可以是任意 'static 类型。
+//! 每种组件类型一个 Storage<T>，组件紧密地存放在 Vec<T> 中（稀疏集合：sparse 按实体编号
+//! 找到组件在 dense 中的位置，删除时用最后一个元素填补空位），遍历时是连续内存访问。
+//!
+//! World 用 HashMap<TypeId, Box<dyn AnyStorage>> 保存不同类型的 Storage，
+//! 取出具体类型时通过 Any::downcast_ref 安全地向下转型，类型不匹配只会得到 None。
+//!
+//! 查询以元组指定组件类型，比如 world.query::<(Position, Velocity)>()，
+//! 以其中组件最少的 Storage 为主遍历，再到其余 Storage 中按编号直接查找。
+use std::any::{type_name, Any, TypeId};
+use std::collections::HashMap;
+use std::fmt;
+
+/// 实体编号被回收复用时代数加一，旧的 Entity 因此失效
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Entity {
+    index: u32,
+    generation: u32,
+}
+
+impl Entity {
+    pub fn index(self) -> u32 {
+        self.index
+    }
+
+    pub fn generation(self) -> u32 {
+        self.generation
+    }
+}
+
+impl fmt::Display for Entity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}v{}", self.index, self.generation)
+    }
+}
+
+/// 一种组件类型的紧密存储
+pub struct Storage<T> {
+    dense: Vec<T>,
+    entities: Vec<Entity>,
+    /// 按实体编号索引，值是组件在 dense 中的位置
+    sparse: Vec<Option<usize>>,
+}
+
+impl<T> Default for Storage<T> {
+    fn default() -> Self {
+        Storage {
+            dense: Vec::new(),
+            entities: Vec::new(),
+            sparse: Vec::new(),
+        }
+    }
+}
+
+impl<T> Storage<T> {
+    fn slot(&self, entity: Entity) -> Option<usize> {
+        let slot = (*self.sparse.get(entity.index as usize)?)?;
+        // 编号相同但代数不同，说明是之前被删除的实体
+        if self.entities[slot] == entity {
+            Some(slot)
+        } else {
+            None
+        }
+    }
+
+    pub fn get(&self, entity: Entity) -> Option<&T> {
+        self.slot(entity).map(|slot| &self.dense[slot])
+    }
+
+    pub fn get_mut(&mut self, entity: Entity) -> Option<&mut T> {
+        self.slot(entity).map(move |slot| &mut self.dense[slot])
+    }
+
+    fn insert(&mut self, entity: Entity, value: T) -> Option<T> {
+        if let Some(slot) = self.slot(entity) {
+            return Some(std::mem::replace(&mut self.dense[slot], value));
+        }
+        let index = entity.index as usize;
+        if self.sparse.len() <= index {
+            self.sparse.resize(index + 1, None);
+        }
+        self.sparse[index] = Some(self.dense.len());
+        self.dense.push(value);
+        self.entities.push(entity);
+        None
+    }
+
+    fn remove(&mut self, entity: Entity) -> Option<T> {
+        let slot = self.slot(entity)?;
+        self.sparse[entity.index as usize] = None;
+        self.entities.swap_remove(slot);
+        let value = self.dense.swap_remove(slot);
+        // 原来的最后一个组件被移到了 slot
+        if let Some(moved) = self.entities.get(slot) {
+            self.sparse[moved.index as usize] = Some(slot);
+        }
+        Some(value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.dense.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.dense.is_empty()
+    }
+
+    /// 拥有这种组件的实体，顺序和 iter 一致
+    pub fn entities(&self) -> &[Entity] {
+        &self.entities
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, &T)> {
+        self.entities.iter().copied().zip(self.dense.iter())
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Entity, &mut T)> {
+        self.entities.iter().copied().zip(self.dense.iter_mut())
+    }
+}
+
+/// 擦除了组件类型的 Storage，World 通过它完成与具体类型无关的操作
+trait AnyStorage {
+    fn remove_entity(&mut self, entity: Entity);
+    fn get_any(&self, entity: Entity) -> Option<&dyn Any>;
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    fn type_name(&self) -> &'static str;
+}
+
+impl<T: 'static> AnyStorage for Storage<T> {
+    fn remove_entity(&mut self, entity: Entity) {
+        self.remove(entity);
+    }
+
+    fn get_any(&self, entity: Entity) -> Option<&dyn Any> {
+        self.get(entity).map(|value| value as &dyn Any)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn type_name(&self) -> &'static str {
+        type_name::<T>()
+    }
+}
+
+#[derive(Default)]
+pub struct World {
+    generations: Vec<u32>,
+    alive: Vec<bool>,
+    free: Vec<u32>,
+    storages: HashMap<TypeId, Box<dyn AnyStorage>>,
+}
+
+impl World {
+    pub fn new() -> Self {
+        World::default()
+    }
+
+    /// 优先复用被删除实体的编号
+    pub fn spawn(&mut self) -> Entity {
+        match self.free.pop() {
+            Some(index) => {
+                self.alive[index as usize] = true;
+                Entity {
+                    index,
+                    generation: self.generations[index as usize],
+                }
+            }
+            None => {
+                let index = self.generations.len() as u32;
+                self.generations.push(0);
+                self.alive.push(true);
+                Entity {
+                    index,
+                    generation: 0,
+                }
+            }
+        }
+    }
+
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        let index = entity.index as usize;
+        self.alive.get(index) == Some(&true) && self.generations[index] == entity.generation
+    }
+
+    /// 删除实体和它的所有组件，实体已经不存在时返回 false
+    pub fn despawn(&mut self, entity: Entity) -> bool {
+        if !self.is_alive(entity) {
+            return false;
+        }
+        for storage in self.storages.values_mut() {
+            storage.remove_entity(entity);
+        }
+        let index = entity.index as usize;
+        self.alive[index] = false;
+        self.generations[index] = self.generations[index].wrapping_add(1);
+        self.free.push(entity.index);
+        true
+    }
+
+    pub fn len(&self) -> usize {
+        self.alive.iter().filter(|&&alive| alive).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 添加或替换组件，返回被替换的旧值。实体已经被删除时 panic
+    pub fn insert<T: 'static>(&mut self, entity: Entity, component: T) -> Option<T> {
+        assert!(self.is_alive(entity), "entity {} is not alive", entity);
+        self.storages
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(Storage::<T>::default()))
+            .as_any_mut()
+            .downcast_mut::<Storage<T>>()
+            .expect("storage is keyed by its component type")
+            .insert(entity, component)
+    }
+
+    pub fn remove<T: 'static>(&mut self, entity: Entity) -> Option<T> {
+        self.storage_mut::<T>()?.remove(entity)
+    }
+
+    pub fn get<T: 'static>(&self, entity: Entity) -> Option<&T> {
+        self.storage::<T>()?.get(entity)
+    }
+
+    pub fn get_mut<T: 'static>(&mut self, entity: Entity) -> Option<&mut T> {
+        self.storage_mut::<T>()?.get_mut(entity)
+    }
+
+    pub fn has<T: 'static>(&self, entity: Entity) -> bool {
+        self.get::<T>(entity).is_some()
+    }
+
+    pub fn storage<T: 'static>(&self) -> Option<&Storage<T>> {
+        self.storages
+            .get(&TypeId::of::<T>())?
+            .as_any()
+            .downcast_ref()
+    }
+
+    pub fn storage_mut<T: 'static>(&mut self) -> Option<&mut Storage<T>> {
+        self.storages
+            .get_mut(&TypeId::of::<T>())?
+            .as_any_mut()
+            .downcast_mut()
+    }
+
+    /// 在运行时才知道组件类型时使用，调用者再用 downcast_ref 取出具体类型
+    pub fn get_dyn(&self, entity: Entity, type_id: TypeId) -> Option<&dyn Any> {
+        self.storages.get(&type_id)?.get_any(entity)
+    }
+
+    /// 实体拥有的所有组件的类型名和值，按类型名排序
+    pub fn components(&self, entity: Entity) -> Vec<(&'static str, &dyn Any)> {
+        let mut components: Vec<_> = self
+            .storages
+            .values()
+            .filter_map(|storage| Some((storage.type_name(), storage.get_any(entity)?)))
+            .collect();
+        components.sort_by_key(|&(name, _)| name);
+        components
+    }
+
+    pub fn query<'w, Q: Query<'w>>(&'w self) -> QueryIter<'w, Q> {
+        let storages = Q::storages(self);
+        let entities = match &storages {
+            Some(storages) => Q::driver(storages),
+            None => &[],
+        };
+        QueryIter {
+            storages,
+            entities: entities.iter(),
+        }
+    }
+}
+
+/// 可以作为查询的组件类型元组
+pub trait Query<'w> {
+    type Item;
+    type Storages;
+
+    /// 有任何一种组件还没有 Storage 时返回 None，此时查询结果为空
+    fn storages(world: &'w World) -> Option<Self::Storages>;
+    /// 组件最少的 Storage 中的实体，只有它们才可能满足查询
+    fn driver(storages: &Self::Storages) -> &'w [Entity];
+    fn fetch(storages: &Self::Storages, entity: Entity) -> Option<Self::Item>;
+}
+
+macro_rules! impl_query {
+    ($($t:ident),+) => {
+        impl<'w, $($t: 'static),+> Query<'w> for ($($t,)+) {
+            type Item = ($(&'w $t,)+);
+            type Storages = ($(&'w Storage<$t>,)+);
+
+            #[allow(non_snake_case)]
+            fn storages(world: &'w World) -> Option<Self::Storages> {
+                Some(($(world.storage::<$t>()?,)+))
+            }
+
+            #[allow(non_snake_case)]
+            fn driver(storages: &Self::Storages) -> &'w [Entity] {
+                let ($($t,)+) = *storages;
+                let candidates = [$($t.entities()),+];
+                candidates.iter().copied().min_by_key(|e| e.len()).unwrap_or(&[])
+            }
+
+            #[allow(non_snake_case)]
+            fn fetch(storages: &Self::Storages, entity: Entity) -> Option<Self::Item> {
+                let ($($t,)+) = *storages;
+                Some(($($t.get(entity)?,)+))
+            }
+        }
+    };
+}
+
+impl_query!(A);
+impl_query!(A, B);
+impl_query!(A, B, C);
+impl_query!(A, B, C, D);
+
+pub struct QueryIter<'w, Q: Query<'w>> {
+    storages: Option<Q::Storages>,
+    entities: std::slice::Iter<'w, Entity>,
+}
+
+impl<'w, Q: Query<'w>> Iterator for QueryIter<'w, Q> {
+    type Item = (Entity, Q::Item);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let storages = self.storages.as_ref()?;
+        self.entities
+            .by_ref()
+            .find_map(|&entity| Some((entity, Q::fetch(storages, entity)?)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.entities.len()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Entity, World};
+    use std::any::TypeId;
+    use std::rc::Rc;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Position(f32, f32);
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Velocity(f32, f32);
+
+    #[derive(Debug, PartialEq)]
+    struct Name(&'static str);
+
+    /// 不带数据的标签组件
+    #[derive(Debug, PartialEq)]
+    struct Player;
+
+    fn sorted<T>(mut items: Vec<(Entity, T)>) -> Vec<(Entity, T)> {
+        items.sort_by_key(|&(e, _)| e);
+        items
+    }
+
+    #[test]
+    fn test_components() {
+        let mut world = World::new();
+        let a = world.spawn();
+        let b = world.spawn();
+        assert_eq!(world.insert(a, Position(0.0, 0.0)), None);
+        world.insert(a, Name("a"));
+        world.insert(b, Name("b"));
+        assert_eq!(
+            world.insert(a, Position(1.0, 2.0)),
+            Some(Position(0.0, 0.0))
+        );
+
+        assert_eq!(world.get::<Position>(a), Some(&Position(1.0, 2.0)));
+        assert_eq!(world.get::<Position>(b), None);
+        assert_eq!(world.get::<Velocity>(a), None);
+        assert!(world.has::<Name>(b));
+
+        world.get_mut::<Position>(a).unwrap().0 += 10.0;
+        assert_eq!(world.get::<Position>(a), Some(&Position(11.0, 2.0)));
+
+        assert_eq!(world.remove::<Name>(a), Some(Name("a")));
+        assert_eq!(world.remove::<Name>(a), None);
+        // 删除 a 的组件后 b 的组件被移动到了前面，仍然能找到
+        assert_eq!(world.get::<Name>(b), Some(&Name("b")));
+        assert_eq!(world.storage::<Name>().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_despawn() {
+        let mut world = World::new();
+        let a = world.spawn();
+        let b = world.spawn();
+        world.insert(a, Name("a"));
+        world.insert(b, Name("b"));
+        let shared = Rc::new(());
+        world.insert(a, Rc::clone(&shared));
+
+        assert!(world.despawn(a));
+        assert!(!world.despawn(a));
+        assert!(!world.is_alive(a));
+        assert_eq!(world.len(), 1);
+        // 组件随实体一起被释放
+        assert_eq!(Rc::strong_count(&shared), 1);
+        assert_eq!(world.get::<Name>(a), None);
+        assert_eq!(world.get::<Name>(b), Some(&Name("b")));
+
+        // 编号被复用，但旧的 Entity 不会访问到新实体的组件
+        let c = world.spawn();
+        assert_eq!(c.index(), a.index());
+        assert_eq!(c.generation(), a.generation() + 1);
+        world.insert(c, Name("c"));
+        assert_eq!(world.get::<Name>(a), None);
+        assert_eq!(world.get::<Name>(c), Some(&Name("c")));
+        assert_eq!(c.to_string(), "0v1");
+    }
+
+    #[test]
+    #[should_panic(expected = "not alive")]
+    fn test_insert_on_dead_entity() {
+        let mut world = World::new();
+        let a = world.spawn();
+        world.despawn(a);
+        world.insert(a, Player);
+    }
+
+    #[test]
+    fn test_query() {
+        let mut world = World::new();
+        let mut moving = Vec::new();
+        for i in 0..10 {
+            let e = world.spawn();
+            world.insert(e, Position(i as f32, 0.0));
+            if i % 2 == 0 {
+                world.insert(e, Velocity(1.0, i as f32));
+                moving.push(e);
+            }
+            if i == 4 {
+                world.insert(e, Player);
+            }
+        }
+        world.despawn(moving[1]);
+
+        let result = sorted(world.query::<(Position, Velocity)>().collect());
+        assert_eq!(result.len(), 4);
+        for (e, (p, v)) in &result {
+            assert!(moving.contains(e));
+            assert_eq!(v.1, p.0);
+        }
+
+        assert_eq!(world.query::<(Player, Position, Velocity)>().count(), 1);
+        let players: Vec<_> = world
+            .query::<(Position, Player)>()
+            .map(|(e, (p, _))| (e, *p))
+            .collect();
+        assert_eq!(players, vec![(moving[2], Position(4.0, 0.0))]);
+
+        assert_eq!(world.query::<(Position,)>().count(), 9);
+        // 没有任何实体拥有的组件类型
+        assert_eq!(world.query::<(Position, Name)>().count(), 0);
+
+        // 按实体修改组件
+        let updates: Vec<_> = world
+            .query::<(Position, Velocity)>()
+            .map(|(e, (p, v))| (e, Position(p.0 + v.0, p.1 + v.1)))
+            .collect();
+        for (e, p) in updates {
+            world.insert(e, p);
+        }
+        assert_eq!(world.get::<Position>(moving[0]), Some(&Position(1.0, 0.0)));
+
+        for (_, v) in world.storage_mut::<Velocity>().unwrap().iter_mut() {
+            v.0 = 0.0;
+        }
+        assert!(world.query::<(Velocity,)>().all(|(_, (v,))| v.0 == 0.0));
+    }
+
+    #[test]
+    fn test_dynamic_access() {
+        let mut world = World::new();
+        let e = world.spawn();
+        world.insert(e, Position(1.0, 2.0));
+        world.insert(e, 42u32);
+
+        let any = world.get_dyn(e, TypeId::of::<Position>()).unwrap();
+        assert_eq!(any.downcast_ref::<Position>(), Some(&Position(1.0, 2.0)));
+        // 类型不匹配时向下转型失败，而不是得到错误的值
+        assert_eq!(any.downcast_ref::<Velocity>(), None);
+        assert!(world.get_dyn(e, TypeId::of::<Velocity>()).is_none());
+
+        let components = world.components(e);
+        let names: Vec<_> = components.iter().map(|(name, _)| *name).collect();
+        assert_eq!(names.len(), 2);
+        assert!(names[0].ends_with("Position"));
+        assert_eq!(names[1], "u32");
+        assert_eq!(components[1].1.downcast_ref::<u32>(), Some(&42));
+    }
+}
@@ -0,0 +1,399 @@
+//! 运行时工厂
+//! foobar::<T>() 只能构造编译期就确定的类型。Factory 把实现了 Inst 的类型按名字注册，
+//! 运行时根据配置中的 type 选出具体类型，用 Inst::from_config 从键值对构造出实例，
+//! 再转换成统一的 Box<P>（P 通常是某个 trait 对象），新增类型只需要再注册一次，不用修改 match。
+//!
+//! 配置是每行一个 key = value 的文本，# 之后是注释，例如：
+//! ```text
+//! type = circle
+//! radius = 2.5
+//! ```
+use crate::generics::Inst;
+use std::any::type_name;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FactoryError {
+    /// 配置文本中无法解析的行，行号从 1 开始
+    Syntax {
+        line: usize,
+        text: String,
+    },
+    DuplicateKey(String),
+    MissingKey(String),
+    /// 值无法解析成需要的类型
+    InvalidValue {
+        key: String,
+        value: String,
+        expected: &'static str,
+    },
+    /// 值能解析，但不满足类型自身的约束
+    Invalid(String),
+    UnknownType(String),
+    /// 同名类型已经注册过
+    Duplicate(String),
+}
+
+impl fmt::Display for FactoryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FactoryError::Syntax { line, text } => {
+                write!(f, "line {}: expected `key = value`, found `{}`", line, text)
+            }
+            FactoryError::DuplicateKey(key) => write!(f, "key `{}` is set more than once", key),
+            FactoryError::MissingKey(key) => write!(f, "missing key `{}`", key),
+            FactoryError::InvalidValue {
+                key,
+                value,
+                expected,
+            } => write!(
+                f,
+                "invalid value `{}` for key `{}`: expected {}",
+                value, key, expected
+            ),
+            FactoryError::Invalid(reason) => write!(f, "invalid config: {}", reason),
+            FactoryError::UnknownType(name) => write!(f, "unknown type `{}`", name),
+            FactoryError::Duplicate(name) => write!(f, "type `{}` is already registered", name),
+        }
+    }
+}
+
+impl Error for FactoryError {}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Config {
+    values: BTreeMap<String, String>,
+}
+
+impl Config {
+    pub fn new() -> Self {
+        Config::default()
+    }
+
+    pub fn set(mut self, key: &str, value: impl ToString) -> Self {
+        self.values.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    pub fn get_str(&self, key: &str) -> Result<&str, FactoryError> {
+        self.values
+            .get(key)
+            .map(String::as_str)
+            .ok_or_else(|| FactoryError::MissingKey(key.to_string()))
+    }
+
+    /// 按需要的类型解析值
+    pub fn get<T: FromStr>(&self, key: &str) -> Result<T, FactoryError> {
+        let value = self.get_str(key)?;
+        value.parse().map_err(|_| FactoryError::InvalidValue {
+            key: key.to_string(),
+            value: value.to_string(),
+            expected: type_name::<T>(),
+        })
+    }
+
+    /// 没有这个键时使用默认值，有但解析失败时仍然报错
+    pub fn get_or<T: FromStr>(&self, key: &str, default: T) -> Result<T, FactoryError> {
+        if self.values.contains_key(key) {
+            self.get(key)
+        } else {
+            Ok(default)
+        }
+    }
+}
+
+impl FromStr for Config {
+    type Err = FactoryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut values = BTreeMap::new();
+        for (i, line) in s.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) if !key.trim().is_empty() => (key.trim(), value.trim()),
+                _ => {
+                    return Err(FactoryError::Syntax {
+                        line: i + 1,
+                        text: line.to_string(),
+                    })
+                }
+            };
+            if values.insert(key.to_string(), value.to_string()).is_some() {
+                return Err(FactoryError::DuplicateKey(key.to_string()));
+            }
+        }
+        Ok(Config { values })
+    }
+}
+
+type Constructor<P> = Box<dyn Fn(&Config) -> Result<Box<P>, FactoryError>>;
+
+/// 按名字构造 Box<P> 的工厂
+pub struct Factory<P: ?Sized> {
+    constructors: BTreeMap<String, Constructor<P>>,
+}
+
+impl<P: ?Sized> Default for Factory<P> {
+    fn default() -> Self {
+        Factory {
+            constructors: BTreeMap::new(),
+        }
+    }
+}
+
+impl<P: ?Sized> Factory<P> {
+    pub fn new() -> Self {
+        Factory::default()
+    }
+
+    /// 注册类型 T，upcast 把构造出的 T 转换成 Box<P>，一般写作 |t| Box::new(t)
+    pub fn register<T: Inst + 'static>(
+        &mut self,
+        name: &str,
+        upcast: fn(T) -> Box<P>,
+    ) -> Result<(), FactoryError>
+    where
+        P: 'static,
+    {
+        if self.constructors.contains_key(name) {
+            return Err(FactoryError::Duplicate(name.to_string()));
+        }
+        let constructor = move |config: &Config| T::from_config(config).map(upcast);
+        self.constructors
+            .insert(name.to_string(), Box::new(constructor));
+        Ok(())
+    }
+
+    /// 已注册的类型名，按名字排序
+    pub fn names(&self) -> Vec<&str> {
+        self.constructors.keys().map(String::as_str).collect()
+    }
+
+    pub fn build(&self, name: &str, config: &Config) -> Result<Box<P>, FactoryError> {
+        match self.constructors.get(name) {
+            Some(constructor) => constructor(config),
+            None => Err(FactoryError::UnknownType(name.to_string())),
+        }
+    }
+
+    /// 由配置中的 type 决定构造哪个类型
+    pub fn build_config(&self, config: &Config) -> Result<Box<P>, FactoryError> {
+        self.build(config.get_str("type")?, config)
+    }
+
+    pub fn build_str(&self, config: &str) -> Result<Box<P>, FactoryError> {
+        self.build_config(&config.parse()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Config, Factory, FactoryError};
+    use crate::generics::Inst;
+    use std::any::Any;
+    use std::f64::consts::PI;
+
+    trait Shape {
+        fn area(&self) -> f64;
+        fn name(&self) -> String;
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Circle {
+        radius: f64,
+    }
+
+    impl Inst for Circle {
+        fn new(i: i32) -> Self {
+            Circle { radius: i as f64 }
+        }
+
+        fn from_config(config: &Config) -> Result<Self, FactoryError> {
+            let radius: f64 = config.get("radius")?;
+            if radius < 0.0 {
+                return Err(FactoryError::Invalid(format!(
+                    "radius must not be negative, got {}",
+                    radius
+                )));
+            }
+            Ok(Circle { radius })
+        }
+    }
+
+    impl Shape for Circle {
+        fn area(&self) -> f64 {
+            PI * self.radius * self.radius
+        }
+
+        fn name(&self) -> String {
+            format!("circle r={}", self.radius)
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Rect {
+        width: u32,
+        height: u32,
+    }
+
+    impl Inst for Rect {
+        fn new(i: i32) -> Self {
+            Rect {
+                width: i as u32,
+                height: i as u32,
+            }
+        }
+
+        /// height 可以省略，省略时是正方形
+        fn from_config(config: &Config) -> Result<Self, FactoryError> {
+            let width = config.get("width")?;
+            let height = config.get_or("height", width)?;
+            Ok(Rect { width, height })
+        }
+    }
+
+    impl Shape for Rect {
+        fn area(&self) -> f64 {
+            (self.width * self.height) as f64
+        }
+
+        fn name(&self) -> String {
+            format!("rect {}x{}", self.width, self.height)
+        }
+    }
+
+    /// 使用默认的 from_config，只读取 value
+    #[derive(Debug, PartialEq)]
+    struct Square(i32);
+
+    impl Inst for Square {
+        fn new(i: i32) -> Self {
+            Square(i)
+        }
+    }
+
+    impl Shape for Square {
+        fn area(&self) -> f64 {
+            (self.0 * self.0) as f64
+        }
+
+        fn name(&self) -> String {
+            format!("square {}", self.0)
+        }
+    }
+
+    fn shapes() -> Factory<dyn Shape> {
+        let mut factory = Factory::<dyn Shape>::new();
+        factory
+            .register::<Circle>("circle", |c| Box::new(c))
+            .unwrap();
+        factory.register::<Rect>("rect", |r| Box::new(r)).unwrap();
+        factory
+            .register::<Square>("square", |s| Box::new(s))
+            .unwrap();
+        factory
+    }
+
+    #[test]
+    fn test_build_from_config() {
+        let factory = shapes();
+        assert_eq!(factory.names(), vec!["circle", "rect", "square"]);
+
+        let configs = [
+            "type = circle\nradius = 1.5",
+            "# 省略 height\ntype = rect\nwidth = 4",
+            "type = rect\nwidth = 2\nheight = 3 # 米",
+            "type = square\nvalue = 5",
+        ];
+        let built: Vec<String> = configs
+            .iter()
+            .map(|c| factory.build_str(c).unwrap().name())
+            .collect();
+        assert_eq!(
+            built,
+            vec!["circle r=1.5", "rect 4x4", "rect 2x3", "square 5"]
+        );
+        assert_eq!(factory.build_str(configs[3]).unwrap().area(), 25.0);
+
+        let config = Config::new().set("radius", 2);
+        assert_eq!(factory.build("circle", &config).unwrap().area(), PI * 4.0);
+    }
+
+    #[test]
+    fn test_errors() {
+        let factory = shapes();
+        let err = |config: &str| factory.build_str(config).err().unwrap();
+
+        assert_eq!(
+            err("type = hexagon"),
+            FactoryError::UnknownType("hexagon".to_string())
+        );
+        assert_eq!(
+            err("radius = 1"),
+            FactoryError::MissingKey("type".to_string())
+        );
+        assert_eq!(
+            err("type = circle"),
+            FactoryError::MissingKey("radius".to_string())
+        );
+        assert_eq!(
+            err("type = rect\nwidth = -3"),
+            FactoryError::InvalidValue {
+                key: "width".to_string(),
+                value: "-3".to_string(),
+                expected: "u32",
+            }
+        );
+        assert_eq!(
+            err("type = circle\nradius = -1").to_string(),
+            "invalid config: radius must not be negative, got -1"
+        );
+        assert_eq!(
+            err("type = circle\n\nradius"),
+            FactoryError::Syntax {
+                line: 3,
+                text: "radius".to_string()
+            }
+        );
+        assert_eq!(
+            err("type = circle\ntype = rect"),
+            FactoryError::DuplicateKey("type".to_string())
+        );
+
+        let mut factory = shapes();
+        assert_eq!(
+            factory.register::<Circle>("circle", |c| Box::new(c)),
+            Err(FactoryError::Duplicate("circle".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_any_products() {
+        // 产品类型不需要公共 trait 时可以用 dyn Any，再向下转型
+        let mut factory = Factory::<dyn Any>::new();
+        factory
+            .register::<Circle>("circle", |c| Box::new(c))
+            .unwrap();
+        factory
+            .register::<Square>("square", |s| Box::new(s))
+            .unwrap();
+
+        let product = factory.build_str("type = square\nvalue = 3").unwrap();
+        assert_eq!(product.downcast_ref::<Square>(), Some(&Square(3)));
+        assert!(product.downcast_ref::<Circle>().is_none());
+        let config = Config::new().set("value", "x");
+        assert!(matches!(
+            factory.build("square", &config),
+            Err(FactoryError::InvalidValue {
+                expected: "i32",
+                ..
+            })
+        ));
+    }
+}
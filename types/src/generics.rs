@@ -1,5 +1,176 @@
+//! 泛型与 const 泛型
+//! VecN<T, N> 在元素类型 T 和维度 N 上都是泛型的，只要 T 支持相应的运算，VecN 就自动支持向量运算，
+//! 例如 T: Add<Output = T> 时 VecN<T, N> 也实现了 Add<Output = Self>，
+//! 这和 traits 中 sum<T: Add<Output = T>> 的约束方式一致，所以 VecN 可以直接用在这类泛型代码中。
+use crate::factory::{Config, FactoryError};
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Index, IndexMut, Mul, Neg, Sub, SubAssign};
+
+/// Rust 中的泛型属于静多态，编译器会基于使用到的类型进行单态化展开（Monomorphization）
+/// 单态化分发的好处是性能好，没有运行时开销，缺点是容易造成编译后的二进制文件过大
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VecN<T, const N: usize>(pub [T; N]);
+
+/// 二维的 VecN，Point<i32>、Point<f64> 都是可以直接参与运算的数学类型
+pub type Point<T> = VecN<T, 2>;
+
+impl<T> Point<T> {
+    pub fn new(x: T, y: T) -> Self {
+        VecN([x, y])
+    }
+
+    pub fn x(&self) -> &T {
+        &self.0[0]
+    }
+
+    pub fn y(&self) -> &T {
+        &self.0[1]
+    }
+}
+
+impl<T, const N: usize> VecN<T, N> {
+    /// 逐个元素转换类型，适用于任意 U: From<T>
+    /// 通用的 impl<T, U: From<T>> From<VecN<T, N>> for VecN<U, N> 会和标准库的 impl<T> From<T> for T 冲突，
+    /// 所以 From 只为下面 impl_vec_from! 列出的具体数值类型实现
+    pub fn cast<U: From<T>>(self) -> VecN<U, N> {
+        VecN(self.0.map(U::from))
+    }
+
+    /// 两个向量按位置组合，是各种逐元素运算的基础
+    fn zip_with<U, R>(self, rhs: VecN<U, N>, mut f: impl FnMut(T, U) -> R) -> VecN<R, N> {
+        let mut rhs = IntoIterator::into_iter(rhs.0);
+        VecN(self.0.map(|a| f(a, rhs.next().unwrap())))
+    }
+}
+
+impl<T: Copy + Mul<Output = T> + Sum<T>, const N: usize> VecN<T, N> {
+    pub fn dot(self, rhs: Self) -> T {
+        self.zip_with(rhs, |a, b| a * b).0.iter().copied().sum()
+    }
+
+    pub fn norm_squared(self) -> T {
+        self.dot(self)
+    }
+}
+
+impl<T: Copy + Into<f64>, const N: usize> VecN<T, N> {
+    /// 欧几里得长度，统一以 f64 计算
+    pub fn norm(self) -> f64 {
+        self.0.iter().map(|&x| x.into().powi(2)).sum::<f64>().sqrt()
+    }
+}
+
+impl<T, const N: usize> From<[T; N]> for VecN<T, N> {
+    fn from(a: [T; N]) -> Self {
+        VecN(a)
+    }
+}
+
+impl<T, const N: usize> From<VecN<T, N>> for [T; N] {
+    fn from(v: VecN<T, N>) -> Self {
+        v.0
+    }
+}
+
+/// 为元素类型之间的无损转换生成 From，例如 Point<f64>::from(Point<i32>)
+macro_rules! impl_vec_from {
+    ($($from:ty => $($to:ty),+;)+) => {
+        $($(
+            impl<const N: usize> From<VecN<$from, N>> for VecN<$to, N> {
+                fn from(v: VecN<$from, N>) -> Self {
+                    v.cast()
+                }
+            }
+        )+)+
+    };
+}
+
+impl_vec_from! {
+    i8 => i16, i32, i64, f32, f64;
+    i16 => i32, i64, f32, f64;
+    i32 => i64, f64;
+    u8 => u16, u32, u64, i16, i32, i64, f32, f64;
+    u16 => u32, u64, i32, i64, f32, f64;
+    u32 => u64, i64, f64;
+    f32 => f64;
+}
+
+impl<T, const N: usize> Index<usize> for VecN<T, N> {
+    type Output = T;
+
+    fn index(&self, i: usize) -> &T {
+        &self.0[i]
+    }
+}
+
+impl<T, const N: usize> IndexMut<usize> for VecN<T, N> {
+    fn index_mut(&mut self, i: usize) -> &mut T {
+        &mut self.0[i]
+    }
+}
+
+impl<T: Add<Output = T>, const N: usize> Add for VecN<T, N> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        self.zip_with(rhs, |a, b| a + b)
+    }
+}
+
+impl<T: Sub<Output = T>, const N: usize> Sub for VecN<T, N> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        self.zip_with(rhs, |a, b| a - b)
+    }
+}
+
+/// 数乘
+impl<T: Copy + Mul<Output = T>, const N: usize> Mul<T> for VecN<T, N> {
+    type Output = Self;
+
+    fn mul(self, k: T) -> Self {
+        VecN(self.0.map(|a| a * k))
+    }
+}
+
+impl<T: Neg<Output = T>, const N: usize> Neg for VecN<T, N> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        VecN(self.0.map(|a| -a))
+    }
+}
+
+impl<T: AddAssign, const N: usize> AddAssign for VecN<T, N> {
+    fn add_assign(&mut self, rhs: Self) {
+        for (a, b) in self.0.iter_mut().zip(IntoIterator::into_iter(rhs.0)) {
+            *a += b;
+        }
+    }
+}
+
+impl<T: SubAssign, const N: usize> SubAssign for VecN<T, N> {
+    fn sub_assign(&mut self, rhs: Self) {
+        for (a, b) in self.0.iter_mut().zip(IntoIterator::into_iter(rhs.0)) {
+            *a -= b;
+        }
+    }
+}
+
+/// 通过返回 Self 的关联函数构造实例，具体类型由调用处的类型标注或 turbofish 决定，比如 foobar::<Foo>(10)。
+/// 运行时按名字选择类型见 factory.rs，from_config 默认读取配置中的 value 并交给 new
+pub trait Inst: Sized {
+    fn new(i: i32) -> Self;
+
+    fn from_config(config: &Config) -> Result<Self, FactoryError> {
+        Ok(Self::new(config.get("value")?))
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use super::{Inst, Point, VecN};
     use crate::Baz;
 
     fn foo<T>(x: T) -> T {
@@ -13,34 +184,56 @@ mod tests {
         println!("12")
     }
 
-    /// Rust 中的泛型属于静多态，编译器会基于使用到的类型进行单态化展开（Monomorphization）
-    /// 单态化分发的好处是性能好，没有运行时开销，缺点是容易造成编译后的二进制文件过大
-    #[derive(Debug, PartialEq)]
-    struct Point<T> {
-        x: T,
-        y: T,
-    }
-    impl<T> Point<T> {
-        fn new(x: T, y: T) -> Self {
-            Point { x, y }
-        }
-    }
-
     #[test]
     fn generics_struct() {
         let point1 = Point::new(1, 2);
         let point2 = Point::new("1", "2");
         assert_eq!(point1, Point::new(1, 2));
         assert_eq!(point2, Point::new("1", "2"));
+        assert_eq!(*point2.x(), "1");
+    }
+
+    #[test]
+    fn generics_vector_ops() {
+        let a = Point::new(1, 2);
+        let b = Point::new(3, -4);
+        assert_eq!(a + b, Point::new(4, -2));
+        assert_eq!(a - b, Point::new(-2, 6));
+        assert_eq!(-a, Point::new(-1, -2));
+        assert_eq!(a * 3, Point::new(3, 6));
+        assert_eq!(a.dot(b), -5);
+        assert_eq!(b.norm(), 5.0);
+
+        let mut c = a;
+        c += b;
+        c -= a;
+        assert_eq!(c, b);
+        c[0] = 10;
+        assert_eq!(c[0], 10);
+
+        let v: VecN<f64, 3> = VecN([1.5, 2.0, 2.0]);
+        assert_eq!(v.norm_squared(), 10.25);
+        assert_eq!((v * 2.0).0, [3.0, 4.0, 4.0]);
+    }
+
+    #[test]
+    fn generics_vector_conversions() {
+        let p = Point::<f64>::from(Point::new(1i32, -2));
+        assert_eq!(p, Point::new(1.0, -2.0));
+        let wide: VecN<i64, 4> = VecN([1u8, 2, 3, 255]).into();
+        assert_eq!(<[i64; 4]>::from(wide), [1, 2, 3, 255]);
+        let half: VecN<f64, 3> = VecN([0.5f32, -1.0, 2.0]).into();
+        assert_eq!(half, VecN([0.5, -1.0, 2.0]));
+        // 没有 From 的类型组合仍然可以用 cast
+        let chars: VecN<u32, 2> = VecN(['a', 'b']).cast();
+        assert_eq!(chars, VecN([97, 98]));
+        assert_eq!(VecN::from([1, 2, 3]), VecN([1, 2, 3]));
     }
 
     #[derive(Debug, PartialEq)]
     struct Foo(i32);
     #[derive(Debug, PartialEq)]
     struct Bar(i32, i32);
-    trait Inst {
-        fn new(i: i32) -> Self;
-    }
     impl Inst for Foo {
         fn new(i: i32) -> Self {
             Foo(i)
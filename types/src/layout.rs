@@ -0,0 +1,295 @@
+//! 类型的内存布局报告
+//! main 里的 size_of 断言只覆盖了少数几个类型，这里为一组类型收集大小、对齐、字段偏移，
+//! 以及 Option<T> 是否能利用 T 的 niche（非法值，比如引用不可能为空）做到和 T 一样大。
+//! 报告可以输出为表格或 JSON，表格格式同时也是快照文件 layout.snapshot 的格式：
+//! repr(Rust) 的布局没有稳定保证，编译器升级或类型定义改变后，与快照比较就能发现差异。
+//!
+//! cargo run -- layout [--json] [--check layout.snapshot]
+use crate::generics::Point;
+use crate::units::{Meter, Quantity};
+use crate::{Baz, Foo, Void};
+use std::fmt::{Debug, Write};
+use std::mem::{align_of, offset_of, size_of};
+use std::num::NonZeroU32;
+use std::rc::Rc;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeLayout {
+    pub name: &'static str,
+    pub size: usize,
+    pub align: usize,
+    /// Option<T> 和 T 一样大，说明 T 有 niche 可以用来表示 None
+    pub niche: bool,
+    pub fields: Vec<(&'static str, usize)>,
+}
+
+impl TypeLayout {
+    pub fn of<T>(name: &'static str, fields: Vec<(&'static str, usize)>) -> Self {
+        TypeLayout {
+            name,
+            size: size_of::<T>(),
+            align: align_of::<T>(),
+            niche: size_of::<Option<T>>() == size_of::<T>(),
+            fields,
+        }
+    }
+
+    /// 表格中的一行：name | size | align | niche | fields，没有字段时省略最后一列
+    fn row(&self) -> String {
+        let mut row = format!(
+            "{:<24} | {:>4} | {:>5} | {}",
+            self.name,
+            self.size,
+            self.align,
+            if self.niche { "yes" } else { "no" },
+        );
+        if !self.fields.is_empty() {
+            let fields: Vec<String> = self
+                .fields
+                .iter()
+                .map(|(name, offset)| format!("{}@{}", name, offset))
+                .collect();
+            row = format!("{:<47} | {}", row, fields.join(" "));
+        }
+        row
+    }
+}
+
+macro_rules! layout {
+    ($t:ty) => {
+        TypeLayout::of::<$t>(stringify!($t), Vec::new())
+    };
+    ($t:ty { $($field:tt),* }) => {
+        TypeLayout::of::<$t>(
+            stringify!($t),
+            vec![$((stringify!($field), offset_of!($t, $field))),*],
+        )
+    };
+}
+
+/// 报告覆盖的类型
+pub fn registry() -> Vec<TypeLayout> {
+    vec![
+        // 零大小类型和空类型
+        layout!(()),
+        layout!(Foo),
+        layout!(Void),
+        layout!(Baz { foo, qux, bza }),
+        layout!([Foo; 10]),
+        layout!([Baz; 10]),
+        // 原生类型，bool 和 char 的取值范围有空隙，所以有 niche
+        layout!(bool),
+        layout!(char),
+        layout!(u8),
+        layout!(u32),
+        layout!(u64),
+        layout!(u128),
+        layout!(f64),
+        layout!(NonZeroU32),
+        // 瘦指针和胖指针：切片和 str 多一个长度，trait 对象多一个 vtable 指针
+        layout!(&u32),
+        layout!(&[u32; 5]),
+        layout!(&[u32]),
+        layout!(&mut [u32]),
+        layout!(&str),
+        layout!(*const u8),
+        layout!(*const [u8]),
+        layout!(&dyn Debug),
+        layout!(&dyn Fn()),
+        layout!(Box<u32>),
+        layout!(Box<[u32]>),
+        layout!(Box<dyn Debug>),
+        layout!(Rc<u32>),
+        layout!(fn()),
+        // Option 对 niche 的利用
+        layout!(Option<u32>),
+        layout!(Option<&u32>),
+        layout!(Option<Box<u32>>),
+        layout!(Option<NonZeroU32>),
+        layout!(Option<bool>),
+        layout!(Option<Void>),
+        // 标准库和本 crate 中的类型
+        layout!(String),
+        layout!(Vec<u8>),
+        layout!(Point<f32> { 0 }),
+        layout!(Quantity<f64, Meter>),
+    ]
+}
+
+pub fn to_table(layouts: &[TypeLayout]) -> String {
+    let mut out = format!(
+        "{:<24} | {:>4} | {:>5} | {:<5} | fields\n",
+        "type", "size", "align", "niche"
+    );
+    for layout in layouts {
+        out.push_str(&layout.row());
+        out.push('\n');
+    }
+    out
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+pub fn to_json(layouts: &[TypeLayout]) -> String {
+    let items: Vec<String> = layouts
+        .iter()
+        .map(|layout| {
+            let fields: Vec<String> = layout
+                .fields
+                .iter()
+                .map(|(name, offset)| format!("{}:{}", json_string(name), offset))
+                .collect();
+            format!(
+                "{{\"type\":{},\"size\":{},\"align\":{},\"niche\":{},\"fields\":{{{}}}}}",
+                json_string(layout.name),
+                layout.size,
+                layout.align,
+                layout.niche,
+                fields.join(",")
+            )
+        })
+        .collect();
+    format!("[\n  {}\n]\n", items.join(",\n  "))
+}
+
+/// 按类型名逐行比较快照和实际布局，返回所有差异，空表示一致
+pub fn compare(snapshot: &str, layouts: &[TypeLayout]) -> Vec<String> {
+    let name_of = |line: &str| line.split('|').next().unwrap_or("").trim().to_string();
+    let expected: Vec<&str> = snapshot.lines().skip(1).filter(|l| !l.is_empty()).collect();
+    let mut diffs = Vec::new();
+    for layout in layouts {
+        let actual = layout.row();
+        match expected.iter().find(|line| name_of(line) == layout.name) {
+            Some(line) if line.trim_end() == actual => {}
+            Some(line) => diffs.push(format!("- {}\n+ {}", line.trim_end(), actual)),
+            None => diffs.push(format!("+ {}", actual)),
+        }
+    }
+    for line in &expected {
+        let name = name_of(line);
+        if !layouts.iter().any(|layout| layout.name == name) {
+            diffs.push(format!("- {}", line));
+        }
+    }
+    diffs
+}
+
+/// layout 子命令，返回进程退出码
+pub fn run(args: &[String]) -> i32 {
+    let layouts = registry();
+    let mut args = args.iter();
+    let mut json = false;
+    let mut check = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--check" => match args.next() {
+                Some(path) => check = Some(path.clone()),
+                None => {
+                    eprintln!("--check needs a snapshot path");
+                    return 2;
+                }
+            },
+            other => {
+                eprintln!("unknown option: {}", other);
+                return 2;
+            }
+        }
+    }
+
+    if let Some(path) = check {
+        let snapshot = match std::fs::read_to_string(&path) {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("cannot read {}: {}", path, e);
+                return 2;
+            }
+        };
+        let diffs = compare(&snapshot, &layouts);
+        for diff in &diffs {
+            println!("{}", diff);
+        }
+        return if diffs.is_empty() { 0 } else { 1 };
+    }
+
+    if json {
+        print!("{}", to_json(&layouts));
+    } else {
+        print!("{}", to_table(&layouts));
+    }
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compare, registry, to_json, to_table, TypeLayout};
+
+    /// 快照记录的是 64 位平台上的布局
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn test_matches_snapshot() {
+        let diffs = compare(include_str!("../layout.snapshot"), &registry());
+        assert!(diffs.is_empty(), "layout changed:\n{}", diffs.join("\n"));
+    }
+
+    #[test]
+    fn test_pointer_facts() {
+        let layouts = registry();
+        let get = |name: &str| layouts.iter().find(|l| l.name == name).unwrap();
+        let word = std::mem::size_of::<usize>();
+        assert_eq!(get("&[u32; 5]").size, word);
+        assert_eq!(get("&[u32]").size, 2 * word);
+        assert_eq!(get("&dyn Debug").size, 2 * word);
+        assert_eq!(get("Box<dyn Debug>").size, 2 * word);
+        // 引用的 niche 被 Option 用掉了，再套一层 Option 就需要额外的空间
+        assert!(get("&u32").niche);
+        assert_eq!(get("Option<&u32>").size, word);
+        assert!(!get("Option<&u32>").niche);
+        assert!(!get("u32").niche);
+        assert_eq!(get("Baz").size, 0);
+        assert_eq!(get("Baz").fields.len(), 3);
+    }
+
+    #[test]
+    fn test_output_and_compare() {
+        let layouts = vec![
+            TypeLayout::of::<u16>("u16", Vec::new()),
+            TypeLayout::of::<(u8, u32)>("(u8, u32)", vec![("0", 4), ("1", 0)]),
+        ];
+        let table = to_table(&layouts);
+        assert_eq!(
+            table,
+            "type                     | size | align | niche | fields\n\
+             u16                      |    2 |     2 | no\n\
+             (u8, u32)                |    8 |     4 | no    | 0@4 1@0\n"
+        );
+        assert!(compare(&table, &layouts).is_empty());
+        assert_eq!(
+            to_json(&layouts[..1]),
+            "[\n  {\"type\":\"u16\",\"size\":2,\"align\":2,\"niche\":false,\"fields\":{}}\n]\n"
+        );
+
+        let changed = table.replace("|    2 |     2", "|    4 |     4");
+        assert_eq!(
+            compare(&changed, &layouts),
+            vec!["- u16                      |    4 |     4 | no\n+ u16                      |    2 |     2 | no"]
+        );
+        let diffs = compare(&table, &layouts[1..]);
+        assert_eq!(
+            diffs,
+            vec!["- u16                      |    2 |     2 | no"]
+        );
+    }
+}
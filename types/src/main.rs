@@ -37,10 +37,28 @@
 //!
 //! Rust中的类型推导只能在局部范围内进行推导
 
+mod bigint;
+mod dst;
+mod ecs;
+mod factory;
 mod generics;
+mod layout;
+mod paginate;
+mod plugin;
+mod rational;
+#[cfg(test)]
+mod test_util;
 mod traits;
+mod units;
+mod vtable;
 
 fn main() {
+    // cargo run -- layout 输出类型布局报告，见 layout.rs
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("layout") {
+        std::process::exit(layout::run(&args[1..]));
+    }
+
     // 包含了动态大小类型信息和携带了长度信息的指针，叫做胖指针（Fat Pointer）, &str是一种胖指针
     let str = "Hello Rust";
     let ptr = str.as_ptr();
@@ -63,6 +81,18 @@ fn main() {
     assert_eq!(std::mem::size_of::<&mut [u32; 5]>(), 8);
     assert_eq!(std::mem::size_of::<&mut [u32]>(), 16);
 
+    // 最后一个字段是 [u32] 的结构体也是 DST，指向它的 Box 同样是胖指针，见 dst.rs
+    let hs = dst::HeaderSlice::new_boxed("arr", arr.iter().copied());
+    assert_eq!(hs.slice, arr);
+    assert_eq!(std::mem::size_of_val(&hs), 16);
+    // 把长度存进分配的内存，就能换回普通指针
+    let mut thin = dst::ThinHeaderSlice::new("arr", arr.iter().copied());
+    assert_eq!(*thin, arr);
+    assert_eq!(std::mem::size_of_val(&thin), 8);
+    *thin.header_mut() = "thin";
+    thin[0] = 0;
+    assert_eq!((*thin.header(), thin[0]), ("thin", 0));
+
     assert_eq!(std::mem::size_of::<()>(), 0);
     assert_eq!(std::mem::size_of::<Foo>(), 0);
     assert_eq!(std::mem::size_of::<Void>(), 0);
@@ -72,6 +102,13 @@ fn main() {
     assert_eq!(std::mem::size_of::<[Foo; 10]>(), 0);
     assert_eq!(std::mem::size_of::<[Baz; 10]>(), 0);
 
+    // 单位标记也是零大小类型，带单位的物理量和原始数值一样大
+    assert_eq!(std::mem::size_of::<units::Meter>(), 0);
+    assert_eq!(
+        std::mem::size_of::<units::Quantity<f64, units::Meter>>(),
+        std::mem::size_of::<f64>()
+    );
+
     for i in vec![(); 10] {
         print!("{:?}", i);
     }
@@ -82,6 +119,16 @@ fn main() {
     void_enum();
 
     turbofish();
+
+    numeric_tower();
+
+    physics();
+
+    trait_objects();
+
+    ecs_world();
+
+    pages();
 }
 
 fn turbofish() {
@@ -97,6 +144,226 @@ fn turbofish() {
     let a_pos = a.is_positive();
 }
 
+fn numeric_tower() {
+    use bigint::BigInt;
+    use rational::Rational;
+    use traits::{Add2, Div2, Mul2, Sub2};
+
+    // 关联类型 Output 决定结果类型，比两个操作数都宽，见 traits.rs
+    let x: u16 = Add2::add(255u8, 255u8);
+    assert_eq!(x, 510);
+    let x: i16 = Sub2::sub(0u8, 255u8);
+    assert_eq!(x, -255);
+    let x: i32 = Mul2::mul(255u8, i8::MIN);
+    assert_eq!(x, -32640);
+    let x: i16 = Div2::div(i8::MIN, -1i8);
+    assert_eq!(x, 128);
+    // 128 位已经无法再加宽，溢出时得到 None
+    assert_eq!(Add2::add(u128::MAX, 1u8), None);
+    // 需要更大的范围时换成任意精度整数，见 bigint.rs
+    let fact: BigInt = (1..=30u32).map(BigInt::from).product();
+    assert_eq!(fact.to_string(), "265252859812191058636308480000000");
+    let big = BigInt::from(u128::MAX) + BigInt::one();
+    assert_eq!(format!("{:x}", big), format!("1{:032}", 0));
+
+    // 除法不想丢掉精度时用有理数，见 rational.rs
+    let third = Rational::new(1, 3);
+    let sum: Rational<i32> = vec![third; 3].into_iter().sum();
+    assert!(sum.is_integer());
+    assert_eq!(Rational::new(6, -4).to_string(), "-3/2");
+    let x = -Rational::new(7, 2);
+    assert_eq!((x.numer(), x.denom(), x.floor()), (-7, 2, -4));
+    assert_eq!(x.recip() * x, Rational::from(1));
+    assert!(third < Rational::new(1, 2));
+    assert_eq!(third.to_f64(), 1.0 / 3.0);
+    let pi = Rational::from_f64(std::f64::consts::PI, 1000);
+    assert_eq!(pi, Some(Rational::new(355, 113)));
+}
+
+fn physics() {
+    use units::{
+        Dim, Dimensionless, Hertz, Joule, Kilogram, Meter, MeterPerSecond, MeterPerSecond2, Newton,
+        Quantity, Second, SquareMeter, N3, P1, P3, Z,
+    };
+
+    // 单位写在类型里，乘除时由编译器推导出结果的单位，见 units.rs
+    let d = Quantity::<f64, Meter>::new(100.0);
+    let t = Quantity::<f64, Second>::new(8.0);
+    let m = Quantity::<f64, Kilogram>::new(2.0);
+    let v: Quantity<f64, MeterPerSecond> = d / t;
+    let a: Quantity<f64, MeterPerSecond2> = v / t;
+    let jerk: Quantity<f64, Dim<P1, N3, Z>> = a / t;
+    let f: Quantity<f64, Newton> = m * a;
+    let e: Quantity<f64, Joule> = f * d;
+    assert_eq!(v.value(), 12.5);
+    assert_eq!(e.to_string(), "312.5 m^2 s^-2 kg");
+    assert_eq!(format!("{:?}", jerk), "0.1953125 m s^-3");
+
+    let area: Quantity<f64, SquareMeter> = d * d;
+    let volume: Quantity<f64, Dim<P3, Z, Z>> = area * d;
+    assert_eq!(volume.value(), 1e6);
+    let half = Quantity::<f64, Meter>::new(50.0);
+    let ratio: Quantity<f64, Dimensionless> = d / half;
+    let freq: Quantity<f64, Hertz> = ratio / t;
+    assert_eq!(freq.to_string(), "0.25 s^-1");
+}
+
+struct Hello(String);
+
+impl traits::Bar for Hello {
+    fn baz(&self) {
+        println!("hello, {}", self.0);
+    }
+}
+
+impl generics::Inst for Hello {
+    fn new(i: i32) -> Self {
+        Hello(i.to_string())
+    }
+
+    fn from_config(config: &factory::Config) -> Result<Self, factory::FactoryError> {
+        let name: String = config.get_or("name", "world".to_string())?;
+        if name.is_empty() {
+            return Err(factory::FactoryError::Invalid("name is empty".to_string()));
+        }
+        Ok(Hello(name))
+    }
+}
+
+fn trait_objects() {
+    use std::mem::{align_of, size_of};
+    use traits::Bar;
+
+    // 手动拼出的 trait 对象：数据指针加 'static 的虚表指针，见 vtable.rs
+    let hello = vtable::ManualDyn::new(Hello("vtable".to_string()));
+    hello.baz();
+    assert_eq!(hello.size_of_val(), size_of::<String>());
+    assert_eq!(hello.align_of_val(), align_of::<String>());
+    let other = vtable::ManualDyn::from_box(Box::new(Hello("box".to_string())));
+    other.baz();
+    println!("{:?}", other.vtable());
+
+    // 不同的 Bar 实现放进同一张插件表，按依赖顺序启动，见 plugin.rs
+    let mut plugins = plugin::PluginRegistry::new();
+    let hello = |name: &str| Box::new(Hello(name.to_string()));
+    plugins.register("db", hello("db"), &[]).unwrap();
+    plugins
+        .register("web", hello("web"), &["db", "log"])
+        .unwrap();
+    plugins.register("log", hello("log"), &[]).unwrap();
+    assert_eq!(plugins.dependencies("web"), Some(vec!["db", "log"]));
+    assert_eq!(plugins.start(), Ok(vec!["db", "log", "web"]));
+    plugins.resolve("web").unwrap().baz();
+
+    plugins.disable("log").unwrap();
+    assert_eq!(plugins.is_enabled("log"), Some(false));
+    let err = plugins.startup_order().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "plugin `web` depends on `log`, which is disabled"
+    );
+    plugins.enable("log").unwrap();
+    plugins.apply_config("db, log # web 暂时不启动").unwrap();
+    assert_eq!(
+        plugins.list(),
+        vec![("db", true), ("log", true), ("web", false)]
+    );
+
+    // 运行时按名字选择要构造的类型，见 factory.rs
+    let mut factory = factory::Factory::<dyn Bar>::new();
+    factory.register::<Hello>("hello", |h| Box::new(h)).unwrap();
+    assert_eq!(factory.names(), vec!["hello"]);
+    factory.build_str("type = hello # 没有 name").unwrap().baz();
+    let config = factory::Config::new().set("name", "factory");
+    assert_eq!(config.get::<String>("name").as_deref(), Ok("factory"));
+    factory.build("hello", &config).unwrap().baz();
+    let err = factory.build_config(&config.set("type", "bye")).err();
+    assert_eq!(
+        err,
+        Some(factory::FactoryError::UnknownType("bye".to_string()))
+    );
+    // 编译期就确定类型时直接用 Inst::new
+    let hello: Hello = generics::Inst::new(42);
+    hello.baz();
+}
+
+fn ecs_world() {
+    use ecs::World;
+    use std::any::TypeId;
+
+    #[derive(Debug, PartialEq)]
+    struct Position(i32, i32);
+    struct Velocity(i32, i32);
+
+    // 组件按类型分开紧密存放，查询时以元组指定组件类型，见 ecs.rs
+    let mut world = World::new();
+    let ball = world.spawn();
+    let wall = world.spawn();
+    world.insert(ball, Position(0, 0));
+    world.insert(ball, Velocity(1, 2));
+    world.insert(wall, Position(5, 5));
+    for (_, (pos, vel)) in world.query::<(Position, Velocity)>() {
+        println!("({}, {}) -> ({}, {})", pos.0, pos.1, vel.0, vel.1);
+    }
+    if let Some(storage) = world.storage_mut::<Position>() {
+        for (_, pos) in storage.iter_mut() {
+            pos.0 += 1;
+        }
+    }
+    world.get_mut::<Velocity>(ball).unwrap().1 = 0;
+    assert_eq!(world.get::<Velocity>(ball).map(|v| v.1), Some(0));
+    assert_eq!(world.get(wall), Some(&Position(6, 5)));
+    assert!(!world.has::<Velocity>(wall));
+
+    // 运行时才知道组件类型时通过 Any 向下转型
+    let pos = world.get_dyn(ball, TypeId::of::<Position>()).unwrap();
+    assert_eq!(pos.downcast_ref(), Some(&Position(1, 0)));
+    println!("{} has {} components", ball, world.components(ball).len());
+
+    assert!(world.remove::<Velocity>(ball).is_some());
+    assert!(world.despawn(ball));
+    assert!(!world.is_alive(ball));
+    // 编号被复用，代数加一
+    let next = world.spawn();
+    assert_eq!((next.index(), next.generation()), (ball.index(), 1));
+    let storage = world.storage::<Position>().unwrap();
+    assert_eq!(storage.entities(), &[wall]);
+    assert_eq!((storage.len(), world.len()), (1, 2));
+    assert!(!storage.is_empty() && !world.is_empty());
+    assert_eq!(storage.iter().count(), 1);
+    assert_eq!(storage.get(wall), Some(&Position(6, 5)));
+}
+
+fn pages() {
+    use paginate::{MyPaginate, Page, PageError, Paginate, PerPage};
+
+    // 实现了 Page 和 PerPage 的类型自动获得 Paginate，见 paginate.rs
+    let items: Vec<u32> = (1..=25).collect();
+    let mut p = MyPaginate::default();
+    assert_eq!(p.paginate_slice(&items), Ok(&items[..10]));
+    p.set_skip_page(2);
+    assert_eq!(p.page(), 3);
+    let last: Vec<u32> = p.paginate(items.iter().copied()).unwrap().collect();
+    assert_eq!(last, [21, 22, 23, 24, 25]);
+    let info = p.info(items.len()).unwrap();
+    assert_eq!(
+        (info.total_pages, info.has_prev, info.has_next),
+        (3, true, false)
+    );
+    assert!(p.has_prev() && !p.has_next(items.len()));
+
+    p.set_per_page(p.max_per_page() + 1);
+    assert_eq!(
+        p.total_pages(items.len()),
+        Err(PageError::PerPageTooLarge {
+            per_page: 101,
+            max: 100
+        })
+    );
+    let err = MyPaginate::new(4, 10).info(items.len()).unwrap_err();
+    assert_eq!(err.to_string(), "page 4 is out of range 1..=3");
+}
+
 fn reset(arr: &mut [u32]) {
     for i in 0..5 {
         arr[i] = (5 - i) as u32;
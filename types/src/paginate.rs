@@ -0,0 +1,317 @@
+//! 分页
+//! Page 记录当前页码，PerPage 记录每页条数和上限，Paginate 继承两者，
+//! 并通过 impl<T: Page + PerPage> Paginate for T 自动为所有同时实现了两者的类型提供分页能力：
+//! 计算总页数、判断前后页、校验页码，以及从切片或 ExactSizeIterator 中取出当前页。
+//!
+//! 页码从 1 开始；没有任何元素时也有 1 页（空页），这样第 1 页总是合法的。
+use std::error::Error;
+use std::fmt;
+use std::iter::{Skip, Take};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageError {
+    /// 页码从 1 开始
+    ZeroPage,
+    ZeroPerPage,
+    PerPageTooLarge {
+        per_page: usize,
+        max: usize,
+    },
+    OutOfRange {
+        page: usize,
+        total_pages: usize,
+    },
+}
+
+impl fmt::Display for PageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PageError::ZeroPage => write!(f, "page numbers start at 1"),
+            PageError::ZeroPerPage => write!(f, "per page must be at least 1"),
+            PageError::PerPageTooLarge { per_page, max } => {
+                write!(f, "per page {} exceeds the limit of {}", per_page, max)
+            }
+            PageError::OutOfRange { page, total_pages } => {
+                write!(f, "page {} is out of range 1..={}", page, total_pages)
+            }
+        }
+    }
+}
+
+impl Error for PageError {}
+
+pub trait Page {
+    fn page(&self) -> usize;
+    fn set_page(&mut self, page: usize);
+}
+
+pub trait PerPage {
+    fn per_page(&self) -> usize;
+    fn set_per_page(&mut self, per_page: usize);
+
+    /// 每页条数的上限，防止一次取出过多数据
+    fn max_per_page(&self) -> usize {
+        100
+    }
+}
+
+/// 某一页在整个集合中的位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageInfo {
+    pub page: usize,
+    pub per_page: usize,
+    pub total_items: usize,
+    pub total_pages: usize,
+    pub has_prev: bool,
+    pub has_next: bool,
+}
+
+/// Rust不支持传统面向对象的继承，而是支持trait继承，使用trait继承可以减少重复简化编程，方便组合
+pub trait Paginate: Page + PerPage {
+    /// 校验每页条数，返回合法的值
+    fn checked_per_page(&self) -> Result<usize, PageError> {
+        let (per_page, max) = (self.per_page(), self.max_per_page());
+        match per_page {
+            0 => Err(PageError::ZeroPerPage),
+            n if n > max => Err(PageError::PerPageTooLarge { per_page, max }),
+            n => Ok(n),
+        }
+    }
+
+    fn total_pages(&self, total_items: usize) -> Result<usize, PageError> {
+        let per_page = self.checked_per_page()?;
+        Ok(total_items.div_ceil(per_page).max(1))
+    }
+
+    fn has_prev(&self) -> bool {
+        self.page() > 1
+    }
+
+    fn has_next(&self, total_items: usize) -> bool {
+        match self.total_pages(total_items) {
+            Ok(total_pages) => self.page() < total_pages,
+            Err(_) => false,
+        }
+    }
+
+    /// 校验当前页码和每页条数，返回当前页的位置信息
+    fn info(&self, total_items: usize) -> Result<PageInfo, PageError> {
+        let page = self.page();
+        let per_page = self.checked_per_page()?;
+        let total_pages = self.total_pages(total_items)?;
+        if page == 0 {
+            return Err(PageError::ZeroPage);
+        }
+        if page > total_pages {
+            return Err(PageError::OutOfRange { page, total_pages });
+        }
+        Ok(PageInfo {
+            page,
+            per_page,
+            total_items,
+            total_pages,
+            has_prev: page > 1,
+            has_next: page < total_pages,
+        })
+    }
+
+    /// 当前页在切片中对应的部分
+    fn paginate_slice<'a, T>(&self, items: &'a [T]) -> Result<&'a [T], PageError> {
+        let info = self.info(items.len())?;
+        let start = (info.page - 1) * info.per_page;
+        let end = (start + info.per_page).min(items.len());
+        Ok(&items[start..end])
+    }
+
+    /// 当前页在迭代器中对应的部分，只跳过前面的元素，不会收集整个迭代器
+    fn paginate<I>(&self, items: I) -> Result<Take<Skip<I::IntoIter>>, PageError>
+    where
+        I: IntoIterator,
+        I::IntoIter: ExactSizeIterator,
+    {
+        let items = items.into_iter();
+        let info = self.info(items.len())?;
+        Ok(items
+            .skip((info.page - 1) * info.per_page)
+            .take(info.per_page))
+    }
+
+    /// 向后跳过 num 页，不检查是否越界，越界会在取数据时报告
+    fn set_skip_page(&mut self, num: usize) {
+        let page = self.page().saturating_add(num);
+        self.set_page(page);
+    }
+}
+
+/// 为泛型T实现Paginate，空快表示直接使用Paginate的默认实现
+/// 其中泛型T的范围限定是Page+PerPage，即在实现了Page+PerPage的类型的基础上，再为它实现Paginate
+/// 而此处Paginate又继承自Page+PerPage，即表示在Page+PerPage的基础上，添加了Paginate的新增的默认实现
+/// 这样可以对原来已实现了Page+PerPage的类型在不侵入的情况下，新增另一个trait的实现！！
+/// trait继承也可以用于扩展标准库中的方法
+/// 包含trait bound的generics是静态分发
+impl<T: Page + PerPage> Paginate for T {}
+
+/// 默认第 1 页，每页 10 条
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MyPaginate {
+    page: usize,
+    per_page: usize,
+}
+
+impl MyPaginate {
+    pub fn new(page: usize, per_page: usize) -> Self {
+        MyPaginate { page, per_page }
+    }
+}
+
+impl Default for MyPaginate {
+    fn default() -> Self {
+        MyPaginate::new(1, 10)
+    }
+}
+
+impl Page for MyPaginate {
+    fn page(&self) -> usize {
+        self.page
+    }
+
+    fn set_page(&mut self, page: usize) {
+        self.page = page;
+    }
+}
+
+impl PerPage for MyPaginate {
+    fn per_page(&self) -> usize {
+        self.per_page
+    }
+
+    fn set_per_page(&mut self, per_page: usize) {
+        self.per_page = per_page;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MyPaginate, Page, PageError, PageInfo, Paginate, PerPage};
+
+    #[test]
+    fn test_slice_pages() {
+        let items: Vec<u32> = (1..=25).collect();
+        let mut p = MyPaginate::default();
+        assert_eq!(p.total_pages(items.len()), Ok(3));
+        assert_eq!(p.paginate_slice(&items).unwrap(), &items[..10]);
+        assert!(!p.has_prev());
+        assert!(p.has_next(items.len()));
+
+        p.set_skip_page(2);
+        assert_eq!(p.page(), 3);
+        assert_eq!(p.paginate_slice(&items).unwrap(), &[21, 22, 23, 24, 25]);
+        assert!(p.has_prev());
+        assert!(!p.has_next(items.len()));
+        assert_eq!(
+            p.info(items.len()),
+            Ok(PageInfo {
+                page: 3,
+                per_page: 10,
+                total_items: 25,
+                total_pages: 3,
+                has_prev: true,
+                has_next: false,
+            })
+        );
+
+        // 恰好整除时没有多余的空页
+        p.set_per_page(5);
+        assert_eq!(p.total_pages(items.len()), Ok(5));
+        p.set_page(5);
+        assert_eq!(p.paginate_slice(&items).unwrap(), &[21, 22, 23, 24, 25]);
+        assert!(!p.has_next(items.len()));
+    }
+
+    #[test]
+    fn test_iterator_pages() {
+        let p = MyPaginate::new(2, 3);
+        let page: Vec<char> = p
+            .paginate("abcdefgh".chars().collect::<Vec<_>>())
+            .unwrap()
+            .collect();
+        assert_eq!(page, vec!['d', 'e', 'f']);
+        let page: Vec<u32> = p.paginate((0..7u32).map(|x| x * x)).unwrap().collect();
+        assert_eq!(page, vec![9, 16, 25]);
+
+        // 把所有页拼起来就是原来的序列
+        let mut p = MyPaginate::new(1, 4);
+        let mut all = Vec::new();
+        loop {
+            all.extend(p.paginate(0..10).unwrap());
+            if !p.has_next(10) {
+                break;
+            }
+            p.set_skip_page(1);
+        }
+        assert_eq!(all, (0..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_validation() {
+        let items = [1, 2, 3];
+        assert_eq!(
+            MyPaginate::new(0, 10).paginate_slice(&items),
+            Err(PageError::ZeroPage)
+        );
+        assert_eq!(
+            MyPaginate::new(2, 10).paginate_slice(&items),
+            Err(PageError::OutOfRange {
+                page: 2,
+                total_pages: 1
+            })
+        );
+        assert_eq!(
+            MyPaginate::new(1, 0).paginate(items.iter()).err(),
+            Some(PageError::ZeroPerPage)
+        );
+        let err = MyPaginate::new(1, 500).paginate_slice(&items).unwrap_err();
+        assert_eq!(err.to_string(), "per page 500 exceeds the limit of 100");
+        assert!(!MyPaginate::new(1, 0).has_next(3));
+
+        // 空集合只有一页，而且是空页
+        let empty: [u8; 0] = [];
+        assert_eq!(MyPaginate::default().paginate_slice(&empty), Ok(&empty[..]));
+        assert_eq!(MyPaginate::default().total_pages(0), Ok(1));
+    }
+
+    /// 只要实现 Page 和 PerPage，就自动获得 Paginate，也可以改写上限
+    struct Cursor {
+        page: usize,
+    }
+
+    impl Page for Cursor {
+        fn page(&self) -> usize {
+            self.page
+        }
+
+        fn set_page(&mut self, page: usize) {
+            self.page = page;
+        }
+    }
+
+    impl PerPage for Cursor {
+        fn per_page(&self) -> usize {
+            2
+        }
+
+        fn set_per_page(&mut self, _: usize) {}
+
+        fn max_per_page(&self) -> usize {
+            2
+        }
+    }
+
+    #[test]
+    fn test_blanket_impl() {
+        let mut c = Cursor { page: 2 };
+        assert_eq!(c.paginate_slice(&["a", "b", "c"]).unwrap(), &["c"]);
+        c.set_per_page(50);
+        assert_eq!(c.total_pages(3), Ok(2));
+    }
+}
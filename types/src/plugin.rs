@@ -0,0 +1,444 @@
+//! 插件注册表
+//! dynamic_dispatch 只演示了通过 &dyn Bar 调用一个方法，这里把不同类型的 Bar 实现
+//! 以 Box<dyn Bar> 的形式放进同一个表中，按名字查找、启用和禁用，
+//! 启动时再根据插件声明的依赖做拓扑排序，保证依赖总是先于使用它的插件启动。
+//!
+//! 依赖是否存在、是否启用、有没有循环，都在 startup_order 中统一检查，
+//! 所以注册和启用的顺序可以任意，依赖可以在之后才注册。
+use crate::traits::Bar;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PluginError {
+    /// 同名插件已经注册过
+    Duplicate(String),
+    NotFound(String),
+    Disabled(String),
+    /// 启用的插件依赖了一个没有注册的插件
+    MissingDependency {
+        plugin: String,
+        dependency: String,
+    },
+    /// 启用的插件依赖了一个被禁用的插件
+    DisabledDependency {
+        plugin: String,
+        dependency: String,
+    },
+    /// 依赖形成了环，首尾是同一个插件，比如 [a, b, a]
+    Cycle(Vec<String>),
+}
+
+impl fmt::Display for PluginError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PluginError::Duplicate(name) => write!(f, "plugin `{}` is already registered", name),
+            PluginError::NotFound(name) => write!(f, "plugin `{}` is not registered", name),
+            PluginError::Disabled(name) => write!(f, "plugin `{}` is disabled", name),
+            PluginError::MissingDependency { plugin, dependency } => write!(
+                f,
+                "plugin `{}` depends on `{}`, which is not registered",
+                plugin, dependency
+            ),
+            PluginError::DisabledDependency { plugin, dependency } => write!(
+                f,
+                "plugin `{}` depends on `{}`, which is disabled",
+                plugin, dependency
+            ),
+            PluginError::Cycle(path) => write!(f, "dependency cycle: {}", path.join(" -> ")),
+        }
+    }
+}
+
+impl Error for PluginError {}
+
+struct Entry {
+    plugin: Box<dyn Bar>,
+    dependencies: Vec<String>,
+    enabled: bool,
+}
+
+/// 按名字排序保存，list 和启动顺序因此都是确定的
+#[derive(Default)]
+pub struct PluginRegistry {
+    plugins: BTreeMap<String, Entry>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Mark {
+    Visiting,
+    Done,
+}
+
+impl PluginRegistry {
+    pub fn new() -> Self {
+        PluginRegistry::default()
+    }
+
+    /// 注册一个默认启用的插件
+    pub fn register(
+        &mut self,
+        name: &str,
+        plugin: Box<dyn Bar>,
+        dependencies: &[&str],
+    ) -> Result<(), PluginError> {
+        if self.plugins.contains_key(name) {
+            return Err(PluginError::Duplicate(name.to_string()));
+        }
+        let entry = Entry {
+            plugin,
+            dependencies: dependencies.iter().map(|d| d.to_string()).collect(),
+            enabled: true,
+        };
+        self.plugins.insert(name.to_string(), entry);
+        Ok(())
+    }
+
+    /// 取出一个启用的插件
+    pub fn resolve(&self, name: &str) -> Result<&dyn Bar, PluginError> {
+        match self.plugins.get(name) {
+            Some(entry) if entry.enabled => Ok(&*entry.plugin),
+            Some(_) => Err(PluginError::Disabled(name.to_string())),
+            None => Err(PluginError::NotFound(name.to_string())),
+        }
+    }
+
+    fn set_enabled(&mut self, name: &str, enabled: bool) -> Result<(), PluginError> {
+        match self.plugins.get_mut(name) {
+            Some(entry) => {
+                entry.enabled = enabled;
+                Ok(())
+            }
+            None => Err(PluginError::NotFound(name.to_string())),
+        }
+    }
+
+    pub fn enable(&mut self, name: &str) -> Result<(), PluginError> {
+        self.set_enabled(name, true)
+    }
+
+    pub fn disable(&mut self, name: &str) -> Result<(), PluginError> {
+        self.set_enabled(name, false)
+    }
+
+    pub fn is_enabled(&self, name: &str) -> Option<bool> {
+        self.plugins.get(name).map(|entry| entry.enabled)
+    }
+
+    /// 所有插件的名字和启用状态，按名字排序
+    pub fn list(&self) -> Vec<(&str, bool)> {
+        self.plugins
+            .iter()
+            .map(|(name, entry)| (name.as_str(), entry.enabled))
+            .collect()
+    }
+
+    pub fn dependencies(&self, name: &str) -> Option<Vec<&str>> {
+        self.plugins
+            .get(name)
+            .map(|entry| entry.dependencies.iter().map(String::as_str).collect())
+    }
+
+    /// 按配置启用插件：配置中列出的插件启用，其余的禁用。
+    /// 名字之间用逗号或换行分隔，# 之后是注释。有未注册的名字时不做任何修改
+    pub fn apply_config(&mut self, config: &str) -> Result<(), PluginError> {
+        let names: Vec<&str> = config
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or(""))
+            .flat_map(|line| line.split(','))
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .collect();
+        if let Some(name) = names.iter().find(|name| !self.plugins.contains_key(**name)) {
+            return Err(PluginError::NotFound(name.to_string()));
+        }
+        for (name, entry) in self.plugins.iter_mut() {
+            entry.enabled = names.contains(&name.as_str());
+        }
+        Ok(())
+    }
+
+    /// 启用插件的启动顺序：每个插件都排在它的所有依赖之后。
+    /// 没有依赖关系的插件按名字排序，所以结果是确定的
+    pub fn startup_order(&self) -> Result<Vec<&str>, PluginError> {
+        let mut marks = BTreeMap::new();
+        let mut order = Vec::new();
+        let mut path = Vec::new();
+        for (name, entry) in &self.plugins {
+            if entry.enabled {
+                self.visit(name, &mut marks, &mut path, &mut order)?;
+            }
+        }
+        Ok(order)
+    }
+
+    /// 深度优先遍历，后序就是拓扑序；path 记录当前路径，遇到正在访问的节点说明有环
+    fn visit<'a>(
+        &'a self,
+        name: &'a str,
+        marks: &mut BTreeMap<&'a str, Mark>,
+        path: &mut Vec<&'a str>,
+        order: &mut Vec<&'a str>,
+    ) -> Result<(), PluginError> {
+        match marks.get(name) {
+            Some(Mark::Done) => return Ok(()),
+            Some(Mark::Visiting) => {
+                let start = path.iter().position(|&n| n == name).unwrap_or(0);
+                let mut cycle: Vec<String> = path[start..].iter().map(|n| n.to_string()).collect();
+                cycle.push(name.to_string());
+                return Err(PluginError::Cycle(cycle));
+            }
+            None => {}
+        }
+        marks.insert(name, Mark::Visiting);
+        path.push(name);
+        let entry = &self.plugins[name];
+        for dependency in &entry.dependencies {
+            match self.plugins.get_key_value(dependency.as_str()) {
+                Some((key, dep)) if dep.enabled => self.visit(key, marks, path, order)?,
+                Some(_) => {
+                    return Err(PluginError::DisabledDependency {
+                        plugin: name.to_string(),
+                        dependency: dependency.clone(),
+                    })
+                }
+                None => {
+                    return Err(PluginError::MissingDependency {
+                        plugin: name.to_string(),
+                        dependency: dependency.clone(),
+                    })
+                }
+            }
+        }
+        path.pop();
+        marks.insert(name, Mark::Done);
+        order.push(name);
+        Ok(())
+    }
+
+    /// 按启动顺序调用每个启用插件的 baz，返回启动顺序
+    pub fn start(&self) -> Result<Vec<&str>, PluginError> {
+        let order = self.startup_order()?;
+        for name in &order {
+            self.plugins[*name].plugin.baz();
+        }
+        Ok(order)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PluginError, PluginRegistry};
+    use crate::traits::Bar;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    type Log = Rc<RefCell<Vec<String>>>;
+
+    struct Named(&'static str, Log);
+
+    impl Bar for Named {
+        fn baz(&self) {
+            self.1.borrow_mut().push(self.0.to_string());
+        }
+    }
+
+    /// 另一种实现，证明表中可以放不同的类型
+    struct Counter(RefCell<u32>, Log);
+
+    impl Bar for Counter {
+        fn baz(&self) {
+            *self.0.borrow_mut() += 1;
+            self.1
+                .borrow_mut()
+                .push(format!("counter {}", self.0.borrow()));
+        }
+    }
+
+    fn registry(log: &Log, plugins: &[(&'static str, &[&str])]) -> PluginRegistry {
+        let mut registry = PluginRegistry::new();
+        for &(name, deps) in plugins {
+            registry
+                .register(name, Box::new(Named(name, Rc::clone(log))), deps)
+                .unwrap();
+        }
+        registry
+    }
+
+    #[test]
+    fn test_register_and_resolve() {
+        let log = Log::default();
+        let mut registry = registry(&log, &[("web", &["db"]), ("db", &[])]);
+        registry
+            .register(
+                "metrics",
+                Box::new(Counter(RefCell::new(0), Rc::clone(&log))),
+                &[],
+            )
+            .unwrap();
+        assert_eq!(
+            registry.register("db", Box::new(Named("db", Rc::clone(&log))), &[]),
+            Err(PluginError::Duplicate("db".to_string()))
+        );
+
+        registry.resolve("metrics").unwrap().baz();
+        registry.resolve("metrics").unwrap().baz();
+        registry.resolve("web").unwrap().baz();
+        assert_eq!(*log.borrow(), vec!["counter 1", "counter 2", "web"]);
+        assert!(matches!(
+            registry.resolve("cache"),
+            Err(PluginError::NotFound(_))
+        ));
+
+        assert_eq!(
+            registry.list(),
+            vec![("db", true), ("metrics", true), ("web", true)]
+        );
+        registry.disable("metrics").unwrap();
+        assert_eq!(registry.is_enabled("metrics"), Some(false));
+        assert!(matches!(
+            registry.resolve("metrics"),
+            Err(PluginError::Disabled(_))
+        ));
+        registry.enable("metrics").unwrap();
+        assert!(registry.resolve("metrics").is_ok());
+        assert_eq!(
+            registry.enable("cache"),
+            Err(PluginError::NotFound("cache".to_string()))
+        );
+        assert_eq!(registry.dependencies("web"), Some(vec!["db"]));
+    }
+
+    #[test]
+    fn test_startup_order() {
+        let log = Log::default();
+        // 依赖在之后才注册也没有关系
+        let registry = registry(
+            &log,
+            &[
+                ("app", &["web", "auth"]),
+                ("web", &["db", "cache"]),
+                ("auth", &["db"]),
+                ("cache", &[]),
+                ("db", &["config"]),
+                ("config", &[]),
+                ("audit", &[]),
+            ],
+        );
+        let order = registry.start().unwrap();
+        assert_eq!(
+            order,
+            vec!["config", "db", "cache", "web", "auth", "app", "audit"]
+        );
+        assert_eq!(*log.borrow(), order);
+
+        // 每个插件都排在它的依赖之后
+        for (i, name) in order.iter().enumerate() {
+            for dep in registry.dependencies(name).unwrap() {
+                assert!(order[..i].contains(&dep), "{} before {}", dep, name);
+            }
+        }
+    }
+
+    #[test]
+    fn test_dependency_errors() {
+        let log = Log::default();
+        let mut registry = registry(&log, &[("web", &["db"]), ("db", &["pool"])]);
+        assert_eq!(
+            registry.startup_order(),
+            Err(PluginError::MissingDependency {
+                plugin: "db".to_string(),
+                dependency: "pool".to_string()
+            })
+        );
+
+        registry
+            .register("pool", Box::new(Named("pool", Rc::clone(&log))), &[])
+            .unwrap();
+        registry.disable("pool").unwrap();
+        let err = registry.startup_order().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "plugin `db` depends on `pool`, which is disabled"
+        );
+
+        // 禁用的插件不参与启动，它的依赖是否满足也就无关紧要
+        registry.disable("db").unwrap();
+        registry.disable("web").unwrap();
+        assert_eq!(registry.start(), Ok(vec![]));
+        assert!(log.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_cycle() {
+        let log = Log::default();
+        let mut registry = registry(
+            &log,
+            &[("a", &["b"]), ("b", &["c"]), ("c", &["a"]), ("d", &["d"])],
+        );
+        let err = registry.startup_order().unwrap_err();
+        assert_eq!(
+            err,
+            PluginError::Cycle(
+                vec!["a", "b", "c", "a"]
+                    .into_iter()
+                    .map(String::from)
+                    .collect()
+            )
+        );
+        assert_eq!(err.to_string(), "dependency cycle: a -> b -> c -> a");
+
+        registry.disable("a").unwrap();
+        registry.disable("b").unwrap();
+        registry.disable("c").unwrap();
+        assert_eq!(
+            registry.startup_order(),
+            Err(PluginError::Cycle(vec!["d".to_string(), "d".to_string()]))
+        );
+        assert!(log.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_apply_config() {
+        let log = Log::default();
+        let mut registry = registry(
+            &log,
+            &[
+                ("web", &["db"]),
+                ("db", &[]),
+                ("cache", &[]),
+                ("debug", &[]),
+            ],
+        );
+        let config = "
+            # 生产环境
+            db, web
+            cache # 可选
+        ";
+        registry.apply_config(config).unwrap();
+        assert_eq!(
+            registry.list(),
+            vec![
+                ("cache", true),
+                ("db", true),
+                ("debug", false),
+                ("web", true)
+            ]
+        );
+        assert_eq!(registry.start().unwrap(), vec!["cache", "db", "web"]);
+
+        // 未知的名字会让整个配置失效，状态保持不变
+        assert_eq!(
+            registry.apply_config("db, mail"),
+            Err(PluginError::NotFound("mail".to_string()))
+        );
+        assert_eq!(registry.is_enabled("web"), Some(true));
+
+        registry.apply_config("web").unwrap();
+        assert!(matches!(
+            registry.start(),
+            Err(PluginError::DisabledDependency { .. })
+        ));
+    }
+}
@@ -0,0 +1,411 @@
+//! 精确的有理数
+//! Rational<T> 总是保持最简形式：分子分母互质且分母为正，零表示为 0/1，
+//! 所以派生的 PartialEq 和 Hash 就是数值意义上的相等。
+//! 运算前会先约掉公因子以减少中间结果溢出的可能，真正溢出时和 T 本身的运算行为一致。
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::fmt;
+use std::ops::{Add, Div, Mul, Neg, Rem, Sub};
+
+/// Rational 的分子分母需要的整数运算
+pub trait Integer:
+    Copy
+    + Ord
+    + fmt::Display
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Rem<Output = Self>
+{
+    const ZERO: Self;
+    const ONE: Self;
+
+    fn to_f64(self) -> f64;
+
+    fn from_i128(n: i128) -> Option<Self>;
+}
+
+macro_rules! impl_integer {
+    ($($t:ty)*) => {
+        $(impl Integer for $t {
+            const ZERO: Self = 0;
+            const ONE: Self = 1;
+
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+
+            fn from_i128(n: i128) -> Option<Self> {
+                <$t>::try_from(n).ok()
+            }
+        })*
+    };
+}
+
+impl_integer!(i8 i16 i32 i64 i128 isize u8 u16 u32 u64 u128 usize);
+
+fn abs<T: Integer>(x: T) -> T {
+    if x < T::ZERO {
+        T::ZERO - x
+    } else {
+        x
+    }
+}
+
+fn gcd<T: Integer>(mut a: T, mut b: T) -> T {
+    while b != T::ZERO {
+        let r = a % b;
+        a = b;
+        b = r;
+    }
+    abs(a)
+}
+
+/// 向下取整的除法，余数总在 [0, d) 之内，要求 d > 0
+fn div_floor<T: Integer>(n: T, d: T) -> (T, T) {
+    let (q, r) = (n / d, n % d);
+    if r < T::ZERO {
+        (q - T::ONE, r + d)
+    } else {
+        (q, r)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rational<T> {
+    num: T,
+    den: T,
+}
+
+impl<T: Integer> Rational<T> {
+    /// 分母为零时 panic
+    pub fn new(num: T, den: T) -> Self {
+        assert!(den != T::ZERO, "denominator must not be zero");
+        let g = gcd(num, den);
+        let (mut num, mut den) = (num / g, den / g);
+        if den < T::ZERO {
+            num = T::ZERO - num;
+            den = T::ZERO - den;
+        }
+        Rational { num, den }
+    }
+
+    pub fn numer(&self) -> T {
+        self.num
+    }
+
+    pub fn denom(&self) -> T {
+        self.den
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.den == T::ONE
+    }
+
+    /// 倒数，零没有倒数，会 panic
+    pub fn recip(&self) -> Self {
+        Rational::new(self.den, self.num)
+    }
+
+    /// 向下取整
+    pub fn floor(&self) -> T {
+        div_floor(self.num, self.den).0
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.num.to_f64() / self.den.to_f64()
+    }
+
+    /// 分母不超过 max_den 的最佳有理逼近（连分数的渐近分数与中间分数），
+    /// NaN、无穷大以及结果超出 T 的范围时返回 None
+    pub fn from_f64(x: f64, max_den: T) -> Option<Self> {
+        if !x.is_finite() || max_den < T::ONE || x.abs() >= i128::MAX as f64 {
+            return None;
+        }
+        let max_den = i128::try_from(max_den.to_f64() as u128)
+            .unwrap_or(i128::MAX)
+            .max(1);
+        let target = x.abs();
+
+        // p0/q0 和 p1/q1 是相邻的两个渐近分数
+        let (mut p0, mut q0, mut p1, mut q1) = (0i128, 1i128, 1i128, 0i128);
+        let mut rest = target;
+        loop {
+            let a = rest.floor();
+            let next = (a as i128)
+                .checked_mul(q1)
+                .and_then(|aq| aq.checked_add(q0))
+                .filter(|&q2| q2 <= max_den);
+            let q2 = match next {
+                Some(q2) => q2,
+                None => break,
+            };
+            let p2 = match (a as i128)
+                .checked_mul(p1)
+                .and_then(|ap| ap.checked_add(p0))
+            {
+                Some(p2) => p2,
+                None => break,
+            };
+            p0 = p1;
+            q0 = q1;
+            p1 = p2;
+            q1 = q2;
+            let frac = rest - a;
+            if frac == 0.0 || (p1 as f64 / q1 as f64) == target {
+                break;
+            }
+            rest = 1.0 / frac;
+        }
+
+        // 最后一个渐近分数和分母受限的中间分数，取更接近的那个
+        // 第一次迭代的分母总是 1，所以 q1 >= 1
+        let k = (max_den - q0) / q1;
+        let (ps, qs) = (p0 + k * p1, q0 + k * q1);
+        let err = |p: i128, q: i128| (p as f64 / q as f64 - target).abs();
+        let (p, q) = if err(ps, qs) < err(p1, q1) {
+            (ps, qs)
+        } else {
+            (p1, q1)
+        };
+        let p = if x < 0.0 { -p } else { p };
+        Some(Rational::new(T::from_i128(p)?, T::from_i128(q)?))
+    }
+}
+
+impl<T: Integer> From<T> for Rational<T> {
+    fn from(n: T) -> Self {
+        Rational {
+            num: n,
+            den: T::ONE,
+        }
+    }
+}
+
+impl<T: Integer> Default for Rational<T> {
+    fn default() -> Self {
+        Rational::from(T::ZERO)
+    }
+}
+
+impl<T: Integer> PartialOrd for Rational<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// 不做交叉相乘，而是逐项比较连分数展开，所以比较本身永远不会溢出
+impl<T: Integer> Ord for Rational<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let (mut a, mut b, mut c, mut d) = (self.num, self.den, other.num, other.den);
+        let mut flipped = false;
+        loop {
+            let (q1, r1) = div_floor(a, b);
+            let (q2, r2) = div_floor(c, d);
+            let ord = match (q1.cmp(&q2), r1 == T::ZERO, r2 == T::ZERO) {
+                (Ordering::Equal, true, true) => Ordering::Equal,
+                (Ordering::Equal, true, false) => Ordering::Less,
+                (Ordering::Equal, false, true) => Ordering::Greater,
+                (Ordering::Equal, false, false) => {
+                    // r1/b 与 r2/d 的大小关系和 b/r1 与 d/r2 相反
+                    a = b;
+                    b = r1;
+                    c = d;
+                    d = r2;
+                    flipped = !flipped;
+                    continue;
+                }
+                (ord, _, _) => ord,
+            };
+            return if flipped { ord.reverse() } else { ord };
+        }
+    }
+}
+
+impl<T: Integer> Add for Rational<T> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        let g = gcd(self.den, rhs.den);
+        let (bg, dg) = (self.den / g, rhs.den / g);
+        Rational::new(self.num * dg + rhs.num * bg, bg * rhs.den)
+    }
+}
+
+impl<T: Integer> Sub for Rational<T> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        let g = gcd(self.den, rhs.den);
+        let (bg, dg) = (self.den / g, rhs.den / g);
+        Rational::new(self.num * dg - rhs.num * bg, bg * rhs.den)
+    }
+}
+
+impl<T: Integer> Mul for Rational<T> {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        if self.num == T::ZERO || rhs.num == T::ZERO {
+            return Rational::default();
+        }
+        // 先交叉约分，结果已经是最简形式
+        let g1 = gcd(self.num, rhs.den);
+        let g2 = gcd(rhs.num, self.den);
+        Rational::new(
+            (self.num / g1) * (rhs.num / g2),
+            (self.den / g2) * (rhs.den / g1),
+        )
+    }
+}
+
+impl<T: Integer> Div for Rational<T> {
+    type Output = Self;
+
+    /// 除数为零时 panic
+    fn div(self, rhs: Self) -> Self {
+        assert!(rhs.num != T::ZERO, "denominator must not be zero");
+        if self.num == T::ZERO {
+            return Rational::default();
+        }
+        let g1 = gcd(self.num, rhs.num);
+        let g2 = gcd(self.den, rhs.den);
+        Rational::new(
+            (self.num / g1) * (rhs.den / g2),
+            (self.den / g2) * (rhs.num / g1),
+        )
+    }
+}
+
+impl<T: Integer + Neg<Output = T>> Neg for Rational<T> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Rational {
+            num: -self.num,
+            den: self.den,
+        }
+    }
+}
+
+impl<T: Integer> std::iter::Sum for Rational<T> {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Rational::default(), |acc, x| acc + x)
+    }
+}
+
+impl<T: Integer> fmt::Display for Rational<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_integer() {
+            write!(f, "{}", self.num)
+        } else {
+            write!(f, "{}/{}", self.num, self.den)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Rational;
+
+    fn r(num: i64, den: i64) -> Rational<i64> {
+        Rational::new(num, den)
+    }
+
+    #[test]
+    fn test_normalize_and_arithmetic() {
+        assert_eq!(r(2, 4), r(1, 2));
+        assert_eq!(r(3, -6), r(-1, 2));
+        assert_eq!(r(0, -5), Rational::default());
+        assert_eq!(r(-4, -8).denom(), 2);
+        assert_eq!(r(1, 3) + r(1, 6), r(1, 2));
+        assert_eq!(r(1, 3) - r(1, 2), r(-1, 6));
+        assert_eq!(r(2, 3) * r(9, 4), r(3, 2));
+        assert_eq!(r(2, 3) / r(-4, 9), r(-3, 2));
+        assert_eq!(-r(1, 2), r(-1, 2));
+        assert_eq!(r(-7, 2).floor(), -4);
+        assert_eq!(r(7, 2).floor(), 3);
+        assert_eq!(r(7, 2).to_string(), "7/2");
+        assert_eq!(r(-8, 2).to_string(), "-4");
+        // 0.1 + 0.2 在有理数下是精确的
+        let tenth = r(1, 10);
+        assert_eq!(tenth + r(2, 10), r(3, 10));
+        assert_eq!(
+            (0..10).map(|_| tenth).sum::<Rational<i64>>(),
+            Rational::from(1)
+        );
+
+        let u = Rational::<u8>::new(6, 8);
+        assert_eq!(u + u, Rational::new(3, 2));
+        // 先约分再相乘，中间结果不会超出 u8
+        assert_eq!(
+            Rational::<u8>::new(200, 3) * Rational::new(3, 100),
+            Rational::from(2)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "denominator must not be zero")]
+    fn test_zero_denominator() {
+        let _ = r(1, 2) / r(0, 1);
+    }
+
+    #[test]
+    fn test_ordering() {
+        let mut values = vec![
+            r(1, 2),
+            r(-3, 4),
+            r(2, 3),
+            r(5, 7),
+            r(0, 1),
+            r(-1, 3),
+            r(7, 1),
+        ];
+        values.sort();
+        assert_eq!(
+            values,
+            vec![
+                r(-3, 4),
+                r(-1, 3),
+                r(0, 1),
+                r(1, 2),
+                r(2, 3),
+                r(5, 7),
+                r(7, 1)
+            ]
+        );
+        // 交叉相乘会溢出 i64，比较依然正确
+        let a = r(i64::MAX, i64::MAX - 1);
+        let b = r(i64::MAX - 1, i64::MAX - 2);
+        assert!(a < b);
+        assert!(r(i64::MIN + 1, 3) < r(i64::MIN + 2, 3));
+        assert_eq!(r(5, 3).cmp(&r(10, 6)), std::cmp::Ordering::Equal);
+    }
+
+    #[test]
+    fn test_f64_conversion() {
+        assert_eq!(r(1, 4).to_f64(), 0.25);
+        assert_eq!(Rational::from_f64(0.75, 100i64), Some(r(3, 4)));
+        assert_eq!(Rational::from_f64(-2.5, 10i32), Some(Rational::new(-5, 2)));
+        assert_eq!(
+            Rational::from_f64(std::f64::consts::PI, 1000i64),
+            Some(r(355, 113))
+        );
+        assert_eq!(
+            Rational::from_f64(std::f64::consts::PI, 100i64),
+            Some(r(311, 99))
+        );
+        assert_eq!(
+            Rational::from_f64(std::f64::consts::PI, 10i64),
+            Some(r(22, 7))
+        );
+        assert_eq!(Rational::from_f64(0.333, 10i64), Some(r(1, 3)));
+        assert_eq!(Rational::from_f64(0.1, 1_000_000i64), Some(r(1, 10)));
+        assert_eq!(Rational::from_f64(1e-9, 100i64), Some(r(0, 1)));
+        assert_eq!(Rational::from_f64(f64::NAN, 100i64), None);
+        assert_eq!(Rational::from_f64(-1.5, 10u32), None);
+        assert_eq!(Rational::from_f64(1e10, 10i32), None);
+        assert_eq!(Rational::from_f64(42.0, 1u8), Some(Rational::from(42)));
+    }
+}
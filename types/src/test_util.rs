@@ -0,0 +1,13 @@
+//! 测试共用的工具
+
+/// xorshift 伪随机数，测试不依赖外部 crate
+pub struct Rng(pub u64);
+
+impl Rng {
+    pub fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
@@ -9,8 +9,176 @@
 //! 3. 抽象类型：动态分发
 //! 4. 标签trait：对类型的约束，可理解为 `enum Type;` ?
 
+use std::convert::TryFrom;
+
+// RSH default value is Self, Self是每个trait都带有的隐式类型参数，代表当前trait的具体类型
+pub trait Add2<RHS = Self> {
+    /// 关联类型
+    type Output;
+    fn add(self, rhs: RHS) -> Self::Output;
+}
+
+pub trait Sub2<RHS = Self> {
+    type Output;
+    fn sub(self, rhs: RHS) -> Self::Output;
+}
+
+pub trait Mul2<RHS = Self> {
+    type Output;
+    fn mul(self, rhs: RHS) -> Self::Output;
+}
+
+pub trait Div2<RHS = Self> {
+    type Output;
+    fn div(self, rhs: RHS) -> Self::Output;
+}
+
+/// 数值塔：通过关联类型Output让结果类型比两个操作数都宽，从而不会悄悄溢出
+/// 1. 先查提升表得到公共类型：两个操作数都能无损转换过去的最小整数类型，比如 u8 和 i8 的公共类型是 i16
+/// 2. 加、乘、除的结果是公共类型的两倍宽度，n 位整数的和、积和商（包括 MIN / -1）都能放得下
+/// 3. 减法的结果总是有符号的，因为无符号数相减也可能得到负数
+/// 4. 公共类型已经是 128 位时无法再加宽，结果为 Option，溢出时返回 None 而不是回绕
+/// 5. 只要有一个操作数是浮点数，结果就是 f64
+///
+/// usize 和 isize 按 64 位处理，在 32 位平台上得到的结果类型只会更宽，依然不会溢出
+macro_rules! promote {
+    ($($a:ty: $($b:ty => $c:ident),*;)*) => {
+        $($(promote!(@common $a, $b, $c);)*)*
+    };
+    (@common $a:ty, $b:ty, u8) => { promote!(@wide $a, $b, u16, i16); };
+    (@common $a:ty, $b:ty, u16) => { promote!(@wide $a, $b, u32, i32); };
+    (@common $a:ty, $b:ty, u32) => { promote!(@wide $a, $b, u64, i64); };
+    (@common $a:ty, $b:ty, u64) => { promote!(@wide $a, $b, u128, i128); };
+    (@common $a:ty, $b:ty, i8) => { promote!(@wide $a, $b, i16, i16); };
+    (@common $a:ty, $b:ty, i16) => { promote!(@wide $a, $b, i32, i32); };
+    (@common $a:ty, $b:ty, i32) => { promote!(@wide $a, $b, i64, i64); };
+    (@common $a:ty, $b:ty, i64) => { promote!(@wide $a, $b, i128, i128); };
+    (@common $a:ty, $b:ty, u128) => { promote!(@checked $a, $b, u128); };
+    (@common $a:ty, $b:ty, i128) => { promote!(@checked $a, $b, i128); };
+    (@wide $a:ty, $b:ty, $w:ty, $s:ty) => {
+        impl Add2<$b> for $a {
+            type Output = $w;
+            fn add(self, rhs: $b) -> $w {
+                self as $w + rhs as $w
+            }
+        }
+        impl Sub2<$b> for $a {
+            type Output = $s;
+            fn sub(self, rhs: $b) -> $s {
+                self as $s - rhs as $s
+            }
+        }
+        impl Mul2<$b> for $a {
+            type Output = $w;
+            fn mul(self, rhs: $b) -> $w {
+                self as $w * rhs as $w
+            }
+        }
+        /// 除数为零时和原生整数除法一样 panic
+        impl Div2<$b> for $a {
+            type Output = $w;
+            fn div(self, rhs: $b) -> $w {
+                self as $w / rhs as $w
+            }
+        }
+    };
+    // 操作数无法用公共类型表示（比如大于 i128::MAX 的 u128）同样返回 None
+    (@checked $a:ty, $b:ty, $c:ty) => {
+        impl Add2<$b> for $a {
+            type Output = Option<$c>;
+            fn add(self, rhs: $b) -> Option<$c> {
+                <$c>::try_from(self).ok()?.checked_add(<$c>::try_from(rhs).ok()?)
+            }
+        }
+        impl Sub2<$b> for $a {
+            type Output = Option<i128>;
+            fn sub(self, rhs: $b) -> Option<i128> {
+                i128::try_from(self).ok()?.checked_sub(i128::try_from(rhs).ok()?)
+            }
+        }
+        impl Mul2<$b> for $a {
+            type Output = Option<$c>;
+            fn mul(self, rhs: $b) -> Option<$c> {
+                <$c>::try_from(self).ok()?.checked_mul(<$c>::try_from(rhs).ok()?)
+            }
+        }
+        /// 除数为零时返回 None
+        impl Div2<$b> for $a {
+            type Output = Option<$c>;
+            fn div(self, rhs: $b) -> Option<$c> {
+                <$c>::try_from(self).ok()?.checked_div(<$c>::try_from(rhs).ok()?)
+            }
+        }
+    };
+}
+
+// 提升表：每一行是左操作数，每一项是 右操作数 => 公共类型
+promote! {
+    u8: u8 => u8, u16 => u16, u32 => u32, u64 => u64, u128 => u128, usize => u64, i8 => i16, i16 => i16, i32 => i32, i64 => i64, i128 => i128, isize => i64;
+    u16: u8 => u16, u16 => u16, u32 => u32, u64 => u64, u128 => u128, usize => u64, i8 => i32, i16 => i32, i32 => i32, i64 => i64, i128 => i128, isize => i64;
+    u32: u8 => u32, u16 => u32, u32 => u32, u64 => u64, u128 => u128, usize => u64, i8 => i64, i16 => i64, i32 => i64, i64 => i64, i128 => i128, isize => i64;
+    u64: u8 => u64, u16 => u64, u32 => u64, u64 => u64, u128 => u128, usize => u64, i8 => i128, i16 => i128, i32 => i128, i64 => i128, i128 => i128, isize => i128;
+    u128: u8 => u128, u16 => u128, u32 => u128, u64 => u128, u128 => u128, usize => u128, i8 => i128, i16 => i128, i32 => i128, i64 => i128, i128 => i128, isize => i128;
+    usize: u8 => u64, u16 => u64, u32 => u64, u64 => u64, u128 => u128, usize => u64, i8 => i128, i16 => i128, i32 => i128, i64 => i128, i128 => i128, isize => i128;
+    i8: u8 => i16, u16 => i32, u32 => i64, u64 => i128, u128 => i128, usize => i128, i8 => i8, i16 => i16, i32 => i32, i64 => i64, i128 => i128, isize => i64;
+    i16: u8 => i16, u16 => i32, u32 => i64, u64 => i128, u128 => i128, usize => i128, i8 => i16, i16 => i16, i32 => i32, i64 => i64, i128 => i128, isize => i64;
+    i32: u8 => i32, u16 => i32, u32 => i64, u64 => i128, u128 => i128, usize => i128, i8 => i32, i16 => i32, i32 => i32, i64 => i64, i128 => i128, isize => i64;
+    i64: u8 => i64, u16 => i64, u32 => i64, u64 => i128, u128 => i128, usize => i128, i8 => i64, i16 => i64, i32 => i64, i64 => i64, i128 => i128, isize => i64;
+    i128: u8 => i128, u16 => i128, u32 => i128, u64 => i128, u128 => i128, usize => i128, i8 => i128, i16 => i128, i32 => i128, i64 => i128, i128 => i128, isize => i128;
+    isize: u8 => i64, u16 => i64, u32 => i64, u64 => i128, u128 => i128, usize => i128, i8 => i64, i16 => i64, i32 => i64, i64 => i64, i128 => i128, isize => i64;
+}
+
+macro_rules! promote_float {
+    ($($a:ty, $b:ty;)*) => {
+        $(
+            impl Add2<$b> for $a {
+                type Output = f64;
+                fn add(self, rhs: $b) -> f64 {
+                    self as f64 + rhs as f64
+                }
+            }
+            impl Sub2<$b> for $a {
+                type Output = f64;
+                fn sub(self, rhs: $b) -> f64 {
+                    self as f64 - rhs as f64
+                }
+            }
+            impl Mul2<$b> for $a {
+                type Output = f64;
+                fn mul(self, rhs: $b) -> f64 {
+                    self as f64 * rhs as f64
+                }
+            }
+            impl Div2<$b> for $a {
+                type Output = f64;
+                fn div(self, rhs: $b) -> f64 {
+                    self as f64 / rhs as f64
+                }
+            }
+        )*
+    };
+    ($f:ty: $($i:ty),*) => {
+        promote_float!($($f, $i; $i, $f;)*);
+    };
+}
+
+promote_float!(f32, f32; f32, f64; f64, f32; f64, f64;);
+promote_float!(f32: u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+promote_float!(f64: u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+/// 用于演示静态分发和动态分发的对象安全 trait，手动实现的虚表见 vtable.rs
+pub trait Bar {
+    fn baz(&self);
+}
+
 #[cfg(test)]
 mod tests {
+    use super::Bar;
+    use crate::bigint::BigInt;
+    use crate::generics::Point;
+    use crate::paginate::{MyPaginate, Page, Paginate, PerPage};
+    use crate::rational::Rational;
+
     trait Add<RHS, Output> {
         fn add(self, rhs: RHS) -> Output;
     }
@@ -32,13 +200,6 @@ mod tests {
         }
     }
 
-    // RSH default value is Self, Self是每个trait都带有的隐式类型参数，代表当前trait的具体类型
-    trait Add2<RHS = Self> {
-        /// 关联类型
-        type Output;
-        fn add(self, rhs: RHS) -> Self::Output;
-    }
-
     #[test]
     fn test_trait() {
         let (a, b, c, d) = (1i32, 2i32, 3u32, 4u32);
@@ -57,54 +218,73 @@ mod tests {
         assert_eq!(a.add(b), 3u64);
     }
 
-    /// Rust不支持传统面向对象的继承，而是支持trait继承，使用trait继承可以减少重复简化编程，方便组合
-    trait Page {
-        fn set_page(&self, p: i32) {
-            println!("Page Default: 1");
-        }
-    }
-    trait PerPage {
-        fn set_perpage(&self, num: i32) {
-            println!("Per Page Default: 10");
-        }
-    }
-    struct MyPaginate {
-        page: i32,
+    /// 测试模块里的Add和Add2都有add方法，为了不让上面的a.add(b)产生歧义，只在函数内引入Add2
+    #[test]
+    fn test_numeric_tower() {
+        use super::{Add2, Div2, Mul2, Sub2};
+
+        // 结果类型由提升表决定，写错类型无法通过编译
+        let x: u16 = Add2::add(255u8, 255u8);
+        assert_eq!(x, 510);
+        let x: i16 = Sub2::sub(0u8, 255u8);
+        assert_eq!(x, -255);
+        let x: u16 = Mul2::mul(255u8, 255u8);
+        assert_eq!(x, 65025);
+        let x: i16 = Div2::div(i8::MIN, -1i8);
+        assert_eq!(x, 128);
+
+        // 混合宽度和符号：先提升到公共类型再加宽
+        let x: i32 = Mul2::mul(255u8, i8::MIN);
+        assert_eq!(x, -32640);
+        let x: i128 = Add2::add(u32::MAX, -1i16);
+        assert_eq!(x, u32::MAX as i128 - 1);
+        let x: u128 = Mul2::mul(u64::MAX, u64::MAX);
+        assert_eq!(x, u64::MAX as u128 * u64::MAX as u128);
+        let x: i128 = Sub2::sub(0u64, u64::MAX);
+        assert_eq!(x, -(u64::MAX as i128));
+        let x: u128 = Add2::add(usize::MAX, 1u8);
+        assert_eq!(x, usize::MAX as u128 + 1);
+        let x: i128 = Mul2::mul(isize::MIN, isize::MIN);
+        assert_eq!(x, 1i128 << 126);
+
+        // 128 位无法再加宽，溢出时返回 None
+        assert_eq!(Add2::add(u128::MAX, 0u8), Some(u128::MAX));
+        assert_eq!(Add2::add(u128::MAX, 1u8), None);
+        assert_eq!(Sub2::sub(1u128, 2u128), Some(-1i128));
+        assert_eq!(Mul2::mul(u64::MAX, -1i64), Some(-(u64::MAX as i128)));
+        assert_eq!(Mul2::mul(i128::MAX, 2i8), None);
+        assert_eq!(Div2::div(i128::MIN, -1i8), None);
+        assert_eq!(Div2::div(1u128, 0u8), None);
+        assert_eq!(Add2::add(u128::MAX, -1i8), None);
+
+        // 浮点数参与运算时结果为 f64
+        let x: f64 = Add2::add(0.1f32, 1u8);
+        assert_eq!(x, 0.1f32 as f64 + 1.0);
+        let x: f64 = Div2::div(1i32, 4.0f64);
+        assert_eq!(x, 0.25);
+        let x: f64 = Mul2::mul(f32::MAX, f32::MAX);
+        assert!(x.is_finite());
     }
-    impl Page for MyPaginate {}
-    impl PerPage for MyPaginate {}
 
+    /// Rust不支持传统面向对象的继承，而是支持trait继承，使用trait继承可以减少重复简化编程，方便组合
+    /// Page、PerPage 和继承了两者的 Paginate 见 paginate.rs
     #[test]
     fn test_trait_not_inherit() {
-        let my_paginate = MyPaginate { page: 1 };
+        let mut my_paginate = MyPaginate::default();
         my_paginate.set_page(2);
-        my_paginate.set_perpage(100);
-    }
-
-    // trait inherit
-    trait Paginate: Page + PerPage {
-        fn set_skip_page(&self, num: i32) {
-            println!("Skip Page: {:?}", num);
-        }
-    }
-    /// 为泛型T实现Paginate，空快表示直接使用Paginate的默认实现
-    /// 其中泛型T的范围限定是Page+PerPage，即在实现了Page+PerPage的类型的基础上，再为它实现Paginate
-    /// 而此处Paginate又继承自Page+PerPage，即表示在Page+PerPage的基础上，添加了Paginate的新增的默认实现
-    /// 这样可以对原来已实现了Page+PerPage的类型在不侵入的情况下，新增另一个trait的实现！！
-    /// trait继承也可以用于扩展标准库中的方法
-    /// 包含trait bound的generics是静态分发
-    impl<T: Page + PerPage> Paginate for T {
-        //        fn set_skip_page(&self, num: i32) {
-        //            unimplemented!()
-        //        }
+        my_paginate.set_per_page(100);
+        assert_eq!((my_paginate.page(), my_paginate.per_page()), (2, 100));
     }
 
+    /// MyPaginate 只实现了 Page 和 PerPage，Paginate 来自 impl<T: Page + PerPage> Paginate for T
     #[test]
     fn test_trait_inherit() {
-        let my_paginate = MyPaginate { page: 1 };
+        let mut my_paginate = MyPaginate::default();
         my_paginate.set_page(1);
-        my_paginate.set_perpage(100);
+        my_paginate.set_per_page(100);
         my_paginate.set_skip_page(12);
+        assert_eq!(my_paginate.page(), 13);
+        assert_eq!(my_paginate.paginate(0..1250).unwrap().next(), Some(1200));
     }
 
     /// 理解类型限定：编程语言中的Structure Typing，结构化类型，用来判断类型是否等价
@@ -124,15 +304,22 @@ mod tests {
     fn test_trait_bound() {
         assert_eq!(sum(1u32, 2u32), 3u32);
         assert_eq!(sum(1u64, 2u64), 3);
+        // VecN<T, N> 在 T: Add<Output = T> 时同样满足 sum 的约束
+        let (a, b) = (Point::new(1.5, 2.0), Point::new(0.5, -1.0));
+        assert_eq!(sum(a, b), Point::new(2.0, 1.0));
+        // BigInt 按值实现了 std::ops::Add，不会溢出
+        let max = BigInt::from(u64::MAX);
+        assert_eq!(sum(max.clone(), max), BigInt::from(u64::MAX as u128 * 2));
+        assert_eq!(
+            sum(Rational::new(1, 3), Rational::new(1, 6)),
+            Rational::new(1i32, 2)
+        );
     }
 
     /// trait还可以用作抽象类型（Abstract Type），也叫存在类型（Existential Type）
     /// 相对于具体类型，抽象类型不能直接实例化，对于抽象类型，编译器无法确定其确切的功能和所占的空间大小
     #[derive(Debug)]
     struct Foo;
-    trait Bar {
-        fn baz(&self);
-    }
     impl Bar for Foo {
         fn baz(&self) {
             println!("{:?}", self);
@@ -0,0 +1,365 @@
+//! 带量纲的物理量
+//! Quantity<T, U> 只包含一个 T，单位 U 通过零大小的 PhantomData 记录在类型中，
+//! 所以它和 T 的大小完全一样，单位检查全部发生在编译期，运行时没有任何开销。
+//!
+//! 单位 U 是 Dim<L, T, M>，三个参数分别是长度（米）、时间（秒）、质量（千克）的指数，
+//! 指数用类型层面的整数表示（Z、Succ<N>、Pred<N>），相乘时指数相加、相除时指数相减，
+//! 所以 (m / s) * s 的单位就是 m，而 m + s 无法通过编译：
+//! ```text
+//! let d = Quantity::<f64, Meter>::new(1.0);
+//! let t = Quantity::<f64, Second>::new(1.0);
+//! let _ = d + t; // expected `Dim<Succ<Z>, Z, Z>`, found `Dim<Z, Succ<Z>, Z>`
+//! ```
+use std::cmp::Ordering;
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+/// 类型层面的整数：零
+pub struct Z;
+/// 类型层面的整数：N + 1
+pub struct Succ<N>(PhantomData<N>);
+/// 类型层面的整数：N - 1
+pub struct Pred<N>(PhantomData<N>);
+
+pub type P1 = Succ<Z>;
+pub type P2 = Succ<P1>;
+pub type P3 = Succ<P2>;
+pub type N1 = Pred<Z>;
+pub type N2 = Pred<N1>;
+pub type N3 = Pred<N2>;
+
+/// 把类型层面的整数还原成值
+pub trait Exponent {
+    const VALUE: i32;
+}
+
+impl Exponent for Z {
+    const VALUE: i32 = 0;
+}
+
+impl<N: Exponent> Exponent for Succ<N> {
+    const VALUE: i32 = N::VALUE + 1;
+}
+
+impl<N: Exponent> Exponent for Pred<N> {
+    const VALUE: i32 = N::VALUE - 1;
+}
+
+/// 加一，Pred<N> + 1 直接化简为 N，保证每个整数只有一种表示
+pub trait Inc {
+    type Output;
+}
+
+impl Inc for Z {
+    type Output = Succ<Z>;
+}
+
+impl<N> Inc for Succ<N> {
+    type Output = Succ<Succ<N>>;
+}
+
+impl<N> Inc for Pred<N> {
+    type Output = N;
+}
+
+pub trait Dec {
+    type Output;
+}
+
+impl Dec for Z {
+    type Output = Pred<Z>;
+}
+
+impl<N> Dec for Succ<N> {
+    type Output = N;
+}
+
+impl<N> Dec for Pred<N> {
+    type Output = Pred<Pred<N>>;
+}
+
+/// 指数相加：0 + B = B，(A + 1) + B = (A + B) + 1，(A - 1) + B = (A + B) - 1
+pub trait AddExp<Rhs> {
+    type Output;
+}
+
+impl<B> AddExp<B> for Z {
+    type Output = B;
+}
+
+impl<A: AddExp<B>, B> AddExp<B> for Succ<A>
+where
+    A::Output: Inc,
+{
+    type Output = <A::Output as Inc>::Output;
+}
+
+impl<A: AddExp<B>, B> AddExp<B> for Pred<A>
+where
+    A::Output: Dec,
+{
+    type Output = <A::Output as Dec>::Output;
+}
+
+pub trait NegExp {
+    type Output;
+}
+
+impl NegExp for Z {
+    type Output = Z;
+}
+
+impl<N: NegExp> NegExp for Succ<N> {
+    type Output = Pred<N::Output>;
+}
+
+impl<N: NegExp> NegExp for Pred<N> {
+    type Output = Succ<N::Output>;
+}
+
+/// 指数相减：A - B = A + (-B)
+pub trait SubExp<Rhs> {
+    type Output;
+}
+
+impl<A, B> SubExp<B> for A
+where
+    B: NegExp,
+    A: AddExp<B::Output>,
+{
+    type Output = <A as AddExp<B::Output>>::Output;
+}
+
+/// 量纲：长度、时间、质量的指数
+pub struct Dim<L, T, M>(PhantomData<(L, T, M)>);
+
+pub type Dimensionless = Dim<Z, Z, Z>;
+pub type Meter = Dim<P1, Z, Z>;
+pub type Second = Dim<Z, P1, Z>;
+pub type Kilogram = Dim<Z, Z, P1>;
+pub type SquareMeter = Dim<P2, Z, Z>;
+pub type MeterPerSecond = Dim<P1, N1, Z>;
+pub type MeterPerSecond2 = Dim<P1, N2, Z>;
+pub type Hertz = Dim<Z, N1, Z>;
+/// kg·m/s²
+pub type Newton = Dim<P1, N2, P1>;
+/// N·m
+pub type Joule = Dim<P2, N2, P1>;
+
+/// 单位相乘得到的新单位
+pub trait MulUnit<Rhs> {
+    type Output;
+}
+
+impl<L1, T1, M1, L2, T2, M2> MulUnit<Dim<L2, T2, M2>> for Dim<L1, T1, M1>
+where
+    L1: AddExp<L2>,
+    T1: AddExp<T2>,
+    M1: AddExp<M2>,
+{
+    type Output = Dim<L1::Output, T1::Output, M1::Output>;
+}
+
+/// 单位相除得到的新单位
+pub trait DivUnit<Rhs> {
+    type Output;
+}
+
+impl<L1, T1, M1, L2, T2, M2> DivUnit<Dim<L2, T2, M2>> for Dim<L1, T1, M1>
+where
+    L1: SubExp<L2>,
+    T1: SubExp<T2>,
+    M1: SubExp<M2>,
+{
+    type Output = Dim<L1::Output, T1::Output, M1::Output>;
+}
+
+pub struct Quantity<T, U> {
+    value: T,
+    unit: PhantomData<U>,
+}
+
+impl<T, U> Quantity<T, U> {
+    pub fn new(value: T) -> Self {
+        Quantity {
+            value,
+            unit: PhantomData,
+        }
+    }
+
+    pub fn value(self) -> T {
+        self.value
+    }
+}
+
+// 派生的 Clone、PartialEq 等会要求单位标记 U 也实现这些 trait，而 U 只存在于类型中，所以手动实现
+impl<T: Clone, U> Clone for Quantity<T, U> {
+    fn clone(&self) -> Self {
+        Quantity::new(self.value.clone())
+    }
+}
+
+impl<T: Copy, U> Copy for Quantity<T, U> {}
+
+impl<T: PartialEq, U> PartialEq for Quantity<T, U> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl<T: PartialOrd, U> PartialOrd for Quantity<T, U> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.value.partial_cmp(&other.value)
+    }
+}
+
+impl<T: fmt::Debug, L: Exponent, Ti: Exponent, M: Exponent> fmt::Debug
+    for Quantity<T, Dim<L, Ti, M>>
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.value)?;
+        write_unit::<L, Ti, M>(f)
+    }
+}
+
+impl<T: fmt::Display, L: Exponent, Ti: Exponent, M: Exponent> fmt::Display
+    for Quantity<T, Dim<L, Ti, M>>
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.value)?;
+        write_unit::<L, Ti, M>(f)
+    }
+}
+
+/// 形如 " m s^-2 kg"，无量纲时什么也不输出
+fn write_unit<L: Exponent, T: Exponent, M: Exponent>(f: &mut fmt::Formatter) -> fmt::Result {
+    for &(symbol, exp) in &[("m", L::VALUE), ("s", T::VALUE), ("kg", M::VALUE)] {
+        match exp {
+            0 => {}
+            1 => write!(f, " {}", symbol)?,
+            _ => write!(f, " {}^{}", symbol, exp)?,
+        }
+    }
+    Ok(())
+}
+
+/// 只有单位相同的量才能相加减
+impl<T: Add<Output = T>, U> Add for Quantity<T, U> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Quantity::new(self.value + rhs.value)
+    }
+}
+
+impl<T: Sub<Output = T>, U> Sub for Quantity<T, U> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Quantity::new(self.value - rhs.value)
+    }
+}
+
+impl<T: Neg<Output = T>, U> Neg for Quantity<T, U> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Quantity::new(-self.value)
+    }
+}
+
+impl<T: Mul<Output = T>, U1: MulUnit<U2>, U2> Mul<Quantity<T, U2>> for Quantity<T, U1> {
+    type Output = Quantity<T, U1::Output>;
+
+    fn mul(self, rhs: Quantity<T, U2>) -> Self::Output {
+        Quantity::new(self.value * rhs.value)
+    }
+}
+
+impl<T: Div<Output = T>, U1: DivUnit<U2>, U2> Div<Quantity<T, U2>> for Quantity<T, U1> {
+    type Output = Quantity<T, U1::Output>;
+
+    fn div(self, rhs: Quantity<T, U2>) -> Self::Output {
+        Quantity::new(self.value / rhs.value)
+    }
+}
+
+/// 乘以无量纲的标量，单位不变
+impl<T: Mul<Output = T>, U> Mul<T> for Quantity<T, U> {
+    type Output = Self;
+
+    fn mul(self, k: T) -> Self {
+        Quantity::new(self.value * k)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Dimensionless, Exponent, Hertz, Joule, Kilogram, Meter, MeterPerSecond, MeterPerSecond2,
+        Newton, Quantity, Second, SquareMeter, N2, P3,
+    };
+    use std::mem::{align_of, size_of};
+
+    #[test]
+    fn test_zero_cost() {
+        // 单位标记是零大小类型，包装之后大小和对齐都与原始类型相同
+        assert_eq!(size_of::<Meter>(), 0);
+        assert_eq!(size_of::<Newton>(), 0);
+        assert_eq!(size_of::<Quantity<f64, Meter>>(), size_of::<f64>());
+        assert_eq!(size_of::<Quantity<f32, Joule>>(), size_of::<f32>());
+        assert_eq!(size_of::<Quantity<u8, Second>>(), size_of::<u8>());
+        assert_eq!(
+            size_of::<[Quantity<f64, Kilogram>; 10]>(),
+            size_of::<[f64; 10]>()
+        );
+        assert_eq!(align_of::<Quantity<u16, Hertz>>(), align_of::<u16>());
+        assert_eq!(
+            size_of::<Option<Quantity<Box<f64>, Meter>>>(),
+            size_of::<Option<Box<f64>>>()
+        );
+    }
+
+    #[test]
+    fn test_derived_units() {
+        let d = Quantity::<f64, Meter>::new(100.0);
+        let t = Quantity::<f64, Second>::new(8.0);
+        let m = Quantity::<f64, Kilogram>::new(2.0);
+
+        // 结果的单位由类型推导得出，写错单位无法通过编译
+        let v: Quantity<f64, MeterPerSecond> = d / t;
+        assert_eq!(v.value(), 12.5);
+        let a: Quantity<f64, MeterPerSecond2> = v / t;
+        let f: Quantity<f64, Newton> = m * a;
+        let e: Quantity<f64, Joule> = f * d;
+        assert_eq!(e.value(), 2.0 * 12.5 / 8.0 * 100.0);
+        let area: Quantity<f64, SquareMeter> = d * d;
+        assert_eq!(area.value(), 10000.0);
+
+        // 指数会化简：(m / s) * s 就是 m，m / m 是无量纲
+        let back: Quantity<f64, Meter> = v * t;
+        assert_eq!(back, d);
+        let ratio: Quantity<f64, Dimensionless> = d / d;
+        assert_eq!(ratio.value(), 1.0);
+        let freq: Quantity<f64, Hertz> = ratio / t;
+        assert_eq!(freq.value(), 0.125);
+
+        assert_eq!(d + d * 0.5, Quantity::new(150.0));
+        assert_eq!(-(d - d * 2.0), d);
+        assert!(t < Quantity::new(10.0));
+        assert_eq!(P3::VALUE, 3);
+        assert_eq!(N2::VALUE, -2);
+    }
+
+    #[test]
+    fn test_format() {
+        let f = Quantity::<f64, Newton>::new(9.5);
+        assert_eq!(f.to_string(), "9.5 m s^-2 kg");
+        assert_eq!(
+            format!("{:?}", Quantity::<i32, SquareMeter>::new(4)),
+            "4 m^2"
+        );
+        assert_eq!(Quantity::<i32, Dimensionless>::new(3).to_string(), "3");
+    }
+}
@@ -0,0 +1,254 @@
+//! 手动实现的 trait 对象
+//! traits.rs 中用文字描述了 trait 对象的结构：一个数据指针加一个虚表指针，
+//! 虚表中有析构函数、大小、对齐和 trait 方法。这里为 Bar 手动构造出同样的结构：
+//! BarVTable 是 #[repr(C)] 的虚表，字段顺序固定；ManualDyn 相当于 Box<dyn Bar>，
+//! 调用 baz、析构和释放内存都只通过虚表完成，不再需要知道具体类型。
+//!
+//! 编译器生成的虚表布局是未指定的，不能依赖，这里只保证行为上与 dyn Bar 一致。
+use crate::traits::Bar;
+use std::alloc::{self, Layout};
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem::{align_of, size_of};
+use std::ptr;
+
+/// Bar 的虚表，每个实现了 Bar 的类型对应一个 'static 的实例
+#[repr(C)]
+pub struct BarVTable {
+    /// 原地析构数据，但不释放内存
+    pub drop_in_place: unsafe fn(*mut ()),
+    pub size: usize,
+    pub align: usize,
+    pub baz: unsafe fn(*const ()),
+}
+
+impl fmt::Debug for BarVTable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BarVTable")
+            .field("size", &self.size)
+            .field("align", &self.align)
+            .finish()
+    }
+}
+
+/// 这几个函数把擦除了类型的指针还原成 *T，再调用 T 的实现，相当于编译器为虚表生成的垫片
+unsafe fn drop_in_place<T>(data: *mut ()) {
+    ptr::drop_in_place(data as *mut T);
+}
+
+unsafe fn baz<T: Bar>(data: *const ()) {
+    (*(data as *const T)).baz();
+}
+
+struct VTableOf<T>(PhantomData<T>);
+
+impl<T: Bar> VTableOf<T> {
+    /// 常量中的引用会被提升为 'static，不需要在运行时为每个对象分配虚表
+    const VTABLE: &'static BarVTable = &BarVTable {
+        drop_in_place: drop_in_place::<T>,
+        size: size_of::<T>(),
+        align: align_of::<T>(),
+        baz: baz::<T>,
+    };
+}
+
+/// 拥有所有权的 Bar trait 对象，和 Box<dyn Bar> 一样是两个指针大小
+pub struct ManualDyn {
+    data: *mut (),
+    vtable: &'static BarVTable,
+}
+
+impl ManualDyn {
+    pub fn new<T: Bar + 'static>(value: T) -> Self {
+        ManualDyn::from_box(Box::new(value))
+    }
+
+    pub fn from_box<T: Bar + 'static>(value: Box<T>) -> Self {
+        ManualDyn {
+            data: Box::into_raw(value) as *mut (),
+            vtable: VTableOf::<T>::VTABLE,
+        }
+    }
+
+    pub fn vtable(&self) -> &'static BarVTable {
+        self.vtable
+    }
+
+    /// 对应 std::mem::size_of_val(&*box_dyn)
+    pub fn size_of_val(&self) -> usize {
+        self.vtable.size
+    }
+
+    pub fn align_of_val(&self) -> usize {
+        self.vtable.align
+    }
+}
+
+impl Bar for ManualDyn {
+    fn baz(&self) {
+        unsafe { (self.vtable.baz)(self.data) }
+    }
+}
+
+/// 先通过虚表析构数据，再按虚表中的大小和对齐释放 Box 分配的内存；零大小类型没有分配内存
+impl Drop for ManualDyn {
+    fn drop(&mut self) {
+        unsafe {
+            (self.vtable.drop_in_place)(self.data);
+            if self.vtable.size != 0 {
+                let layout = Layout::from_size_align_unchecked(self.vtable.size, self.vtable.align);
+                alloc::dealloc(self.data as *mut u8, layout);
+            }
+        }
+    }
+}
+
+impl fmt::Debug for ManualDyn {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ManualDyn")
+            .field("data", &self.data)
+            .field("vtable", self.vtable)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ManualDyn;
+    use crate::traits::Bar;
+    use std::cell::RefCell;
+    use std::mem::{align_of_val, size_of, size_of_val};
+    use std::rc::Rc;
+
+    type Log = Rc<RefCell<Vec<String>>>;
+
+    struct Counter {
+        name: &'static str,
+        hits: RefCell<u32>,
+        log: Log,
+    }
+
+    impl Bar for Counter {
+        fn baz(&self) {
+            *self.hits.borrow_mut() += 1;
+            let msg = format!("{} baz {}", self.name, self.hits.borrow());
+            self.log.borrow_mut().push(msg);
+        }
+    }
+
+    impl Drop for Counter {
+        fn drop(&mut self) {
+            let msg = format!("{} drop", self.name);
+            self.log.borrow_mut().push(msg);
+        }
+    }
+
+    struct Unit(Log);
+
+    impl Bar for Unit {
+        fn baz(&self) {
+            self.0.borrow_mut().push("unit baz".to_string());
+        }
+    }
+
+    struct Silent;
+
+    impl Bar for Silent {
+        fn baz(&self) {}
+    }
+
+    #[repr(align(64))]
+    struct Aligned(u8, Log);
+
+    impl Bar for Aligned {
+        fn baz(&self) {
+            let msg = format!("aligned baz {} {}", self.0, self as *const _ as usize % 64);
+            self.1.borrow_mut().push(msg);
+        }
+    }
+
+    fn counter(name: &'static str, log: &Log) -> Counter {
+        Counter {
+            name,
+            hits: RefCell::new(0),
+            log: Rc::clone(log),
+        }
+    }
+
+    /// 同样的值分别放进 Box<dyn Bar> 和 ManualDyn，调用和析构产生的日志应当完全相同
+    #[test]
+    fn test_same_behavior_as_dyn() {
+        let log = Log::default();
+        let values: Vec<Box<dyn Bar>> = vec![
+            Box::new(counter("a", &log)),
+            Box::new(Unit(Rc::clone(&log))),
+            Box::new(Aligned(7, Rc::clone(&log))),
+        ];
+        for v in &values {
+            v.baz();
+        }
+        values[0].baz();
+        drop(values);
+        let expected = log.borrow_mut().drain(..).collect::<Vec<_>>();
+
+        let manual = vec![
+            ManualDyn::new(counter("a", &log)),
+            ManualDyn::new(Unit(Rc::clone(&log))),
+            ManualDyn::from_box(Box::new(Aligned(7, Rc::clone(&log)))),
+        ];
+        for v in &manual {
+            v.baz();
+        }
+        manual[0].baz();
+        drop(manual);
+        assert_eq!(*log.borrow(), expected);
+        assert_eq!(
+            expected,
+            vec![
+                "a baz 1",
+                "unit baz",
+                "aligned baz 7 0",
+                "a baz 2",
+                "a drop"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_vtable_matches_dyn() {
+        let log = Log::default();
+        let c = counter("c", &log);
+        let by_ref: &dyn Bar = &c;
+        let manual = ManualDyn::new(counter("c", &log));
+        assert_eq!(manual.size_of_val(), size_of_val(by_ref));
+        assert_eq!(manual.align_of_val(), align_of_val(by_ref));
+
+        let a = Aligned(1, Rc::clone(&log));
+        let by_ref: &dyn Bar = &a;
+        let aligned = ManualDyn::new(Aligned(1, Rc::clone(&log)));
+        assert_eq!(aligned.size_of_val(), size_of_val(by_ref));
+        assert_eq!(aligned.align_of_val(), 64);
+
+        // 零大小类型不分配内存，析构时也不释放
+        let silent = ManualDyn::new(Silent);
+        silent.baz();
+        assert_eq!(silent.size_of_val(), size_of_val(&Silent as &dyn Bar));
+        assert_eq!(silent.size_of_val(), 0);
+
+        // 和 Box<dyn Bar> 一样是胖指针大小
+        assert_eq!(size_of::<ManualDyn>(), size_of::<Box<dyn Bar>>());
+        assert_eq!(size_of::<ManualDyn>(), 2 * size_of::<usize>());
+    }
+
+    #[test]
+    fn test_manual_dyn_is_a_bar() {
+        let log = Log::default();
+        let manual = ManualDyn::new(counter("m", &log));
+        // ManualDyn 自己也实现了 Bar，可以再放进真正的 trait 对象里
+        let nested: &dyn Bar = &manual;
+        nested.baz();
+        manual.baz();
+        drop(manual);
+        assert_eq!(*log.borrow(), vec!["m baz 1", "m baz 2", "m drop"]);
+    }
+}